@@ -0,0 +1,200 @@
+use egui::{text::LayoutJob, Color32, TextFormat};
+
+enum Segment<'a> {
+    Text(&'a str),
+    Codes(&'a str),
+}
+
+// Splits the input into plain text and the parameters of the escape sequences between them
+fn segments(input: &str) -> impl Iterator<Item = Segment<'_>> {
+    let mut rest = input;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+
+        let Some(sequence) = rest.strip_prefix("\x1b[") else {
+            let end = rest.find("\x1b[").unwrap_or(rest.len());
+            let (text, remaining) = rest.split_at(end);
+            rest = remaining;
+            return Some(Segment::Text(text));
+        };
+
+        // Collect the entire escape code
+        let end = sequence
+            .find(|c: char| !c.is_ascii_digit() && c != ';')
+            .unwrap_or(sequence.len());
+        let (codes, remaining) = sequence.split_at(end);
+
+        // Consume the final character ('m' in this case)
+        let mut remaining = remaining.chars();
+        remaining.next();
+        rest = remaining.as_str();
+        Some(Segment::Codes(codes))
+    })
+}
+
+pub fn strip_ansi(input: &str) -> String {
+    segments(input)
+        .filter_map(|segment| match segment {
+            Segment::Text(text) => Some(text),
+            Segment::Codes(_) => None,
+        })
+        .collect()
+}
+
+// egui fonts have no bold weight, so bold text is drawn with the strong text color like `RichText::strong`
+pub fn create_layout_from_terminal_escape_sequence(
+    input: &str,
+    base_format: &TextFormat,
+    strong_color: Color32,
+    job: &mut LayoutJob,
+) {
+    let mut current_format = base_format.clone();
+    let mut bold = false;
+
+    for segment in segments(input) {
+        let escape_code = match segment {
+            Segment::Text(text) => {
+                let mut format = current_format.clone();
+                if bold && format.color == base_format.color {
+                    format.color = strong_color;
+                }
+                job.append(text, 0.0, format);
+                continue;
+            }
+            Segment::Codes(escape_code) => escape_code,
+        };
+
+        // An empty code ("\x1b[m") is the same as a reset
+        let mut codes = escape_code
+            .split(';')
+            .map(|code| code.parse::<u32>().unwrap_or(0));
+        while let Some(code) = codes.next() {
+            match code {
+                0 => {
+                    // Reset
+                    current_format = base_format.clone();
+                    bold = false;
+                }
+                1 => bold = true,                   // Bold on
+                3 => current_format.italics = true, // Italic on
+                4 => current_format.underline = egui::Stroke::new(1.0, current_format.color), // Underline on
+                21 | 22 => bold = false,              // Bold off
+                23 => current_format.italics = false, // Italic off
+                24 => current_format.underline = egui::Stroke::NONE, // Underline off
+                30..=37 => current_format.color = ansi_color(code - 30),
+                39 => current_format.color = base_format.color,
+                40..=47 => current_format.background = ansi_color(code - 40),
+                49 => current_format.background = base_format.background,
+                90..=97 => current_format.color = ansi_color(code - 90 + 8),
+                100..=107 => current_format.background = ansi_color(code - 100 + 8),
+                38 | 48 => {
+                    let Some(color) = extended_ansi_color(&mut codes) else {
+                        break;
+                    };
+                    if code == 38 {
+                        current_format.color = color;
+                    } else {
+                        current_format.background = color;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+// Color from the 256 color palette, the first 16 are the normal and bright terminal colors
+fn ansi_color(index: u32) -> Color32 {
+    match index {
+        0 => Color32::BLACK,
+        1 => Color32::RED,
+        2 => Color32::GREEN,
+        3 => Color32::YELLOW,
+        4 => Color32::BLUE,
+        5 => Color32::from_hex("#FF00FF").unwrap(),
+        6 => Color32::from_hex("#00FFFF").unwrap(),
+        7 => Color32::WHITE,
+        8 => Color32::DARK_GRAY,
+        9 => Color32::LIGHT_RED,
+        10 => Color32::LIGHT_GREEN,
+        11 => Color32::LIGHT_YELLOW,
+        12 => Color32::LIGHT_BLUE,
+        13 => Color32::from_hex("#FF80FF").unwrap(),
+        14 => Color32::from_hex("#80FFFF").unwrap(),
+        15 => Color32::WHITE,
+        16..=231 => {
+            let level = |value: u32| {
+                if value == 0 {
+                    0
+                } else {
+                    (55 + value * 40) as u8
+                }
+            };
+            let index = index - 16;
+            Color32::from_rgb(level(index / 36), level((index / 6) % 6), level(index % 6))
+        }
+        232..=255 => Color32::from_gray((8 + (index - 232) * 10) as u8),
+        _ => TextFormat::default().color,
+    }
+}
+
+// Parses the arguments of 38/48 codes: "5;N" for 256 colors or "2;R;G;B" for truecolor
+fn extended_ansi_color(codes: &mut impl Iterator<Item = u32>) -> Option<Color32> {
+    match codes.next()? {
+        5 => Some(ansi_color(codes.next()?)),
+        2 => {
+            let mut channel = || codes.next().map(|value| value.min(255) as u8);
+            Some(Color32::from_rgb(channel()?, channel()?, channel()?))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Text of each section with its color and background
+    fn sections(input: &str) -> Vec<(String, Color32, Color32)> {
+        let mut job = LayoutJob::default();
+        let base = TextFormat::default();
+        create_layout_from_terminal_escape_sequence(input, &base, Color32::WHITE, &mut job);
+        job.sections
+            .iter()
+            .map(|section| {
+                let text = job.text[section.byte_range.clone()].to_string();
+                (text, section.format.color, section.format.background)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn extended_colors() {
+        let base = TextFormat::default();
+        assert_eq!(
+            sections("\x1b[38;5;196mred\x1b[38;5;9mbright\x1b[0m plain"),
+            vec![
+                ("red".into(), Color32::from_rgb(255, 0, 0), base.background),
+                ("bright".into(), Color32::LIGHT_RED, base.background),
+                (" plain".into(), base.color, base.background),
+            ]
+        );
+        assert_eq!(
+            sections("\x1b[48;5;244mgray\x1b[48;5;21mblue"),
+            vec![
+                ("gray".into(), base.color, Color32::from_gray(128)),
+                ("blue".into(), base.color, Color32::from_rgb(0, 0, 255)),
+            ]
+        );
+        assert_eq!(
+            sections("\x1b[38;2;10;20;300;48;2;1;2;3mtrue"),
+            vec![(
+                "true".into(),
+                Color32::from_rgb(10, 20, 255),
+                Color32::from_rgb(1, 2, 3)
+            )]
+        );
+    }
+}
@@ -1,73 +1,368 @@
+use crate::ansi::{self, create_layout_from_terminal_escape_sequence};
+use crate::diff::{self, Change};
+use crate::export::{self, ExportFormat};
+use crate::fuzzy;
+use crate::live::{self, LiveState};
 use crate::parser::{self, LogEntry, LogLevel};
-use async_std::task::current;
-use chrono::{DateTime, Datelike, Utc};
-use egui::{text::LayoutJob, Color32, FontId, RichText, TextFormat, TextStyle};
-use egui_dock::{DockArea, DockState, Style};
+use crate::share;
+use crate::timestamp::TimestampFormat;
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use egui::{text::LayoutJob, Color32, RichText, TextFormat};
+use egui_dock::{DockArea, DockState, NodeIndex, Style, SurfaceIndex};
 use egui_modal::Modal;
-use std::collections::BTreeMap;
-use std::{
-    io::Read,
-    sync::{Arc, Mutex},
-};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
 use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
 
 struct TabContent {
     title: String,
-    entries: parser::Entries,
+    entries: Arc<parser::Entries>,
+    // Earliest timestamp, relative timestamps count from it
+    start: DateTime<Utc>,
     is_search: bool,
     filter: String,
+    // Space-separated terms whose entries are left out, searching or filtering
+    exclude: String,
+    use_regex: bool,
+    case_sensitive: bool,
+    regex_error: Option<String>,
     enabled_levels: Vec<LogLevel>,
-    //TODO: Move this to reference
-    filtered_entries: Vec<LogEntry>,
+    components: BTreeSet<String>,
+    enabled_components: BTreeSet<String>,
+    show_service: bool,
+    service_counts: BTreeMap<String, usize>,
+    export_format: ExportFormat,
+    strip_ansi: bool,
+    jump_to: String,
+    bookmarks: BTreeSet<EntryId>,
+    notes: BTreeMap<EntryId, String>,
+    // Note being written, saved when confirmed
+    editing_note: Option<(EntryId, String)>,
+    // Hash of the bundle the entries came from, set once the tab is shown
+    bundle: Option<u64>,
+    date_range: (DateTime<Utc>, DateTime<Utc>),
+    synced_date: Option<DateTime<Utc>>,
+    gap_threshold: u64,
+    gaps: Vec<parser::Gap>,
+    current_gap_index: Option<usize>,
+    matches: Vec<usize>,
+    level_counts: Vec<usize>,
+    current_match_index: Option<usize>,
+    // Indices of the entries left by the filters
+    filtered: Vec<usize>,
+    // Entries shown before and after each match when filtering, as with `grep -C`
+    context_lines: usize,
+    // Whether each filtered entry is only shown as context of a match
+    context: Vec<bool>,
+    collapse_duplicates: bool,
+    rows: Vec<Row>,
     heights: Vec<f32>,
+    wrap: bool,
+    // Row height the heights were computed with, changes with the font size
+    text_height: f32,
+    // Estimated characters fitting in the content column when wrapping
+    chars_per_line: Option<usize>,
+    // Rows whose multi-line message is shown in full
+    expanded: BTreeSet<usize>,
+    // Show the lines as they were in the file instead of the table
+    raw: bool,
+    // Read the `key=value` pairs of the messages, see `parser::message_fields`
+    fields: bool,
+    // Space-separated `key=value` terms the entries must all have, `key` alone only needs the field
+    field_filter: String,
+    raw_lines: Vec<(usize, usize)>,
+    // Overview of a whole bundle, shown instead of entries
+    dashboard: Option<Dashboard>,
+    // Most frequent message patterns with their count, computed when first shown
+    patterns: Option<Vec<(usize, String)>>,
+    // Where the entries came from, to open the tab again in the next session
+    source: Option<TabSource>,
+    overview: Overview,
     rx: regex::Regex,
+    query: SearchQuery,
+    index: Option<SearchIndex>,
+    dirty: bool,
+    focus_search: bool,
+    clear_filter: bool,
 }
 
 impl TabContent {
-    fn new(title: String, entries: parser::Entries) -> Self {
+    fn new(title: String, entries: Arc<parser::Entries>) -> Self {
+        let components: BTreeSet<String> = entries
+            .iter()
+            .filter_map(|entry| entry.component.clone())
+            .collect();
+        let show_service = entries.iter().any(|entry| entry.service.is_some());
+        let gap_threshold = 60;
+        let gaps = parser::detect_gaps(&entries, chrono::TimeDelta::seconds(gap_threshold as i64));
+        // Entries of a single file are not necessarily sorted
+        let start = entries
+            .iter()
+            .map(|entry| entry.timestamp)
+            .min()
+            .unwrap_or_default();
         Self {
             title,
             entries,
+            start,
             is_search: true,
             filter: Default::default(),
+            exclude: Default::default(),
+            use_regex: true,
+            case_sensitive: false,
+            regex_error: None,
             enabled_levels: LogLevel::iter()
                 .filter(|x| *x != LogLevel::Unknown)
                 .collect(),
-            filtered_entries: Default::default(),
+            enabled_components: components.clone(),
+            components,
+            show_service,
+            service_counts: Default::default(),
+            export_format: ExportFormat::Txt,
+            strip_ansi: true,
+            jump_to: Default::default(),
+            bookmarks: Default::default(),
+            notes: Default::default(),
+            editing_note: None,
+            bundle: None,
+            date_range: Default::default(),
+            synced_date: None,
+            gap_threshold,
+            gaps,
+            current_gap_index: None,
+            matches: vec![],
+            level_counts: vec![],
+            current_match_index: None,
+            filtered: Default::default(),
+            context_lines: 0,
+            context: vec![],
+            collapse_duplicates: false,
+            rows: vec![],
             heights: vec![],
+            wrap: true,
+            text_height: 0.0,
+            chars_per_line: None,
+            expanded: Default::default(),
+            raw: false,
+            raw_lines: vec![],
+            fields: false,
+            field_filter: Default::default(),
+            dashboard: None,
+            patterns: None,
+            source: None,
+            overview: Default::default(),
             rx: regex::Regex::new("").unwrap(),
+            query: Default::default(),
+            index: None,
+            dirty: true,
+            focus_search: false,
+            clear_filter: false,
+        }
+    }
+
+    // The entries grew, as when following a file
+    fn set_entries(&mut self, entries: Arc<parser::Entries>) {
+        let components: BTreeSet<String> = entries
+            .iter()
+            .filter_map(|entry| entry.component.clone())
+            .collect();
+        for component in components.difference(&self.components) {
+            self.enabled_components.insert(component.clone());
+        }
+        self.components = components;
+        self.start = entries
+            .iter()
+            .map(|entry| entry.timestamp)
+            .min()
+            .unwrap_or_default();
+        self.gaps = parser::detect_gaps(
+            &entries,
+            chrono::TimeDelta::seconds(self.gap_threshold as i64),
+        );
+        self.entries = entries;
+        self.index = None;
+        self.patterns = None;
+        self.dirty = true;
+    }
+
+    fn add_field_filter(&mut self, field: String) {
+        self.fields = true;
+        if !self
+            .field_filter
+            .split_whitespace()
+            .any(|term| term == field)
+        {
+            if !self.field_filter.trim().is_empty() {
+                self.field_filter.push(' ');
+            }
+            self.field_filter += &field;
+            self.dirty = true;
+        }
+    }
+}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct TabFilter {
+    is_search: bool,
+    filter: String,
+    exclude: String,
+    use_regex: bool,
+    case_sensitive: bool,
+    enabled_levels: Vec<LogLevel>,
+    fields: bool,
+    field_filter: String,
+    context_lines: usize,
+}
+
+impl Default for TabFilter {
+    fn default() -> Self {
+        Self::from_tab(&TabContent::new(Default::default(), Default::default()))
+    }
+}
+
+impl TabFilter {
+    // Filtering on a regex for the given levels
+    fn matching(filter: &str, levels: Vec<LogLevel>) -> Self {
+        Self {
+            is_search: false,
+            filter: filter.into(),
+            use_regex: true,
+            enabled_levels: levels,
+            ..Default::default()
+        }
+    }
+
+    fn from_tab(tab: &TabContent) -> Self {
+        Self {
+            is_search: tab.is_search,
+            filter: tab.filter.clone(),
+            exclude: tab.exclude.clone(),
+            use_regex: tab.use_regex,
+            case_sensitive: tab.case_sensitive,
+            enabled_levels: tab.enabled_levels.clone(),
+            fields: tab.fields,
+            field_filter: tab.field_filter.clone(),
+            context_lines: tab.context_lines,
         }
     }
+
+    fn apply(&self, tab: &mut TabContent) {
+        tab.is_search = self.is_search;
+        tab.filter = self.filter.clone();
+        tab.exclude = self.exclude.clone();
+        tab.use_regex = self.use_regex;
+        tab.case_sensitive = self.case_sensitive;
+        tab.enabled_levels = self.enabled_levels.clone();
+        tab.fields = self.fields;
+        tab.field_filter = self.field_filter.clone();
+        tab.context_lines = self.context_lines;
+        tab.dirty = true;
+    }
 }
 
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 pub struct TemplateApp {
+    #[serde(skip)]
     worker: Arc<Mutex<parser::Worker>>,
-    size_text: Arc<Mutex<String>>,
+    #[serde(skip)]
     open_model: bool,
-    logs: parser::Processed,
+    // Loaded bundles, more than one can be open to compare them
+    #[serde(skip)]
+    bundles: Vec<Bundle>,
+    // Name of the bundle being processed
+    #[serde(skip)]
+    loading_name: String,
+    // File of the single log being processed, to follow it once loaded
+    #[serde(skip)]
+    loading_path: Option<std::path::PathBuf>,
+    // Whether the bundle being processed is added to the loaded ones instead of replacing them
+    #[serde(skip)]
+    keep_bundles: bool,
+    #[serde(skip)]
     tree: DockState<TabContent>,
     tab_viewer: TabViewer,
+    #[serde(skip)]
     is_processing: bool,
+    #[serde(skip)]
     last_time: chrono::DateTime<chrono::Utc>,
     service_names: BTreeMap<String, bool>,
+    #[serde(skip)]
+    discovered_services: BTreeSet<String>,
+    #[serde(skip)]
+    pending_bundle: Option<BundleFile>,
+    // Bundle loaded last, to load it again
+    #[serde(skip)]
+    last_bundle: Option<BundleFile>,
+    #[serde(skip)]
+    service_filter: String,
+    // Narrows the services listed in the side panel
+    #[serde(skip)]
+    service_search: String,
+    tab_filters: BTreeMap<String, TabFilter>,
+    // Notes of each tab by tab title, for every bundle they were written for
+    saved_notes: SavedNotes,
+    #[serde(skip)]
+    open_url_window: bool,
+    url: String,
+    #[serde(skip)]
+    open_live_window: bool,
+    // WebSocket the vehicle streams its logs on, or its linux2rest endpoint when polling
+    live_url: String,
+    live_polling: bool,
+    // Seconds between two requests to the linux2rest endpoint
+    poll_interval: f32,
+    #[serde(skip)]
+    show_shortcuts: bool,
+    #[serde(skip)]
+    command_palette: Option<CommandPalette>,
+    // Tabs chosen to be compared, while choosing them
+    #[serde(skip)]
+    compare: Option<Compare>,
+    #[serde(skip)]
+    diff: Option<DiffView>,
+    guess_unknown_levels: bool,
+    dedup_entries: bool,
+    // Extra line formats tried after the stock ones
+    profiles: Vec<parser::Profile>,
+    #[serde(skip)]
+    show_profiles: bool,
+    theme: Theme,
+    // Chosen by the user over the ones of the theme
+    level_colors: Option<LevelColors>,
+    // Least severe level exported by Export all, everything when none
+    export_level: Option<LogLevel>,
+    // Whether Export all keeps to the date range of the tabs
+    export_date_range: bool,
+    keep_unparsed: bool,
+    // Get the attention of the user once a bundle is loaded
+    notify_when_loaded: bool,
+    // Tabs of the last session, opened again with the next bundle
+    layout: Option<DockState<TabLayout>>,
+    // View of the link the web page was opened with, until a bundle with its service is loaded
+    #[serde(skip)]
+    shared_view: Option<share::View>,
+    // Last view written to the URL
+    #[serde(skip)]
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    shared_hash: String,
 }
 
 impl Default for TemplateApp {
     fn default() -> Self {
         Self {
             worker: Arc::new(Mutex::new(Default::default())),
-            size_text: Default::default(),
             open_model: false,
-            logs: Default::default(),
+            bundles: vec![],
+            loading_name: Default::default(),
+            loading_path: None,
+            keep_bundles: false,
             tree: DockState::new(vec![]),
-            tab_viewer: TabViewer {
-                selected_date: None,
-                first_date: chrono::offset::Utc::now()
-                    .with_year(2020)
-                    .unwrap()
-                    .date_naive(),
-                second_date: (chrono::offset::Utc::now() + chrono::Months::new(1)).date_naive(),
-            },
+            tab_viewer: Default::default(),
             is_processing: false,
             last_time: chrono::prelude::Utc::now(),
             service_names: vec![
@@ -89,19 +384,148 @@ impl Default for TemplateApp {
                 "wifi-manage",
             ]
             .iter()
-            .map(|&name| {
-                let value = !name.contains("camera");
-                (name.into(), value)
-            })
+            .map(|&name| (name.into(), default_service_enabled(name)))
             .collect(),
+            discovered_services: Default::default(),
+            pending_bundle: None,
+            last_bundle: None,
+            service_filter: Default::default(),
+            service_search: Default::default(),
+            tab_filters: Default::default(),
+            saved_notes: Default::default(),
+            open_url_window: false,
+            url: Default::default(),
+            open_live_window: false,
+            live_url: Default::default(),
+            live_polling: false,
+            poll_interval: 5.0,
+            show_shortcuts: false,
+            command_palette: None,
+            compare: None,
+            diff: None,
+            guess_unknown_levels: true,
+            profiles: vec![],
+            show_profiles: false,
+            theme: Default::default(),
+            level_colors: None,
+            export_level: None,
+            export_date_range: false,
+            keep_unparsed: false,
+            dedup_entries: true,
+            notify_when_loaded: false,
+            layout: None,
+            shared_view: None,
+            shared_hash: Default::default(),
         }
     }
 }
 
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
 struct TabViewer {
+    #[serde(skip)]
     selected_date: Option<DateTime<Utc>>,
-    first_date: chrono::NaiveDate,
-    second_date: chrono::NaiveDate,
+    // Last entry clicked, shown in full by the inspector
+    #[serde(skip)]
+    selected_entry: Option<SelectedEntry>,
+    first_date: DateTime<Utc>,
+    second_date: DateTime<Utc>,
+    linked_scrolling: bool,
+    show_deltas: bool,
+    show_source_file: bool,
+    show_histogram: bool,
+    monospace: bool,
+    font_size: f32,
+    timestamp_format: TimestampFormat,
+    presets: Vec<FilterPreset>,
+    // Name the filters of a tab are saved as
+    #[serde(skip)]
+    preset_name: String,
+    #[serde(skip)]
+    preset_date_range: bool,
+    // Asked from the context menu of a tab, done once the dock is shown
+    #[serde(skip)]
+    close_tabs: Option<CloseTabs>,
+    // Resolved from the theme every frame
+    #[serde(skip)]
+    palette: Palette,
+}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct FilterPreset {
+    name: String,
+    filter: TabFilter,
+    date_range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+// Investigations common enough to come with the viewer
+fn builtin_presets() -> Vec<FilterPreset> {
+    let preset = |name: &str, filter: &str, minimum: LogLevel| FilterPreset {
+        name: name.into(),
+        filter: TabFilter::matching(filter, levels_from(&minimum)),
+        date_range: None,
+    };
+    vec![
+        preset("Errors and warnings", "", LogLevel::Warn),
+        preset(
+            "Arming failures",
+            r"\b(?:pre-?arm|arm(?:ed|ing)?|disarm(?:ed|ing)?)\b",
+            LogLevel::Info,
+        ),
+        preset(
+            "GPS issues",
+            r"\b(?:gps|gnss|satellites?|hdop|no fix|fix type)\b",
+            LogLevel::Info,
+        ),
+        preset(
+            "Connection problems",
+            r"\b(?:disconnect(?:ed)?|timed? ?out|connection (?:refused|reset|lost)|unreachable)\b",
+            LogLevel::Info,
+        ),
+    ]
+}
+
+struct SelectedEntry {
+    // Title of the tab it was clicked in
+    tab: String,
+    entry: LogEntry,
+}
+
+enum CloseTabs {
+    All,
+    // Every tab but the one with this title
+    Others(String),
+}
+
+impl Default for TabViewer {
+    fn default() -> Self {
+        Self {
+            selected_date: None,
+            selected_entry: None,
+            first_date: chrono::offset::Utc::now()
+                .with_year(2020)
+                .unwrap()
+                .date_naive()
+                .and_time(chrono::NaiveTime::MIN)
+                .and_utc(),
+            second_date: (chrono::offset::Utc::now() + chrono::Months::new(1))
+                .date_naive()
+                .and_time(end_of_minute(23, 59))
+                .and_utc(),
+            linked_scrolling: false,
+            show_deltas: false,
+            show_source_file: false,
+            show_histogram: true,
+            monospace: false,
+            font_size: egui::TextStyle::Body.resolve(&egui::Style::default()).size,
+            timestamp_format: Default::default(),
+            presets: vec![],
+            preset_name: Default::default(),
+            preset_date_range: false,
+            close_tabs: None,
+            palette: Palette::DEFAULT,
+        }
+    }
 }
 
 impl egui_dock::TabViewer for TabViewer {
@@ -111,307 +535,3715 @@ impl egui_dock::TabViewer for TabViewer {
         tab.title.clone().into()
     }
 
-    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
-        let entries = &tab.entries;
-        let is_search = &mut tab.is_search;
-        let filter = &mut tab.filter;
-        let filtered_entries = &mut tab.filtered_entries;
-        let rx = &mut tab.rx;
-        let heights = &mut tab.heights;
-
-        let text_height = egui::TextStyle::Body
-            .resolve(ui.style())
-            .size
-            .max(ui.spacing().interact_size.y);
-
-        #[inline]
-        fn reset_filter(
-            entries: &parser::Entries,
-            filtered_entries: &mut Vec<LogEntry>,
-            heights: &mut Vec<f32>,
-            text_height: f32,
-        ) {
-            *filtered_entries = entries.clone();
-            *heights = filtered_entries
-                .iter()
-                .map(|entry| {
-                    (entry.message.lines().count() as f32 * text_height * 0.9).max(text_height)
-                })
-                .collect();
+    fn context_menu(
+        &mut self,
+        ui: &mut egui::Ui,
+        tab: &mut Self::Tab,
+        _surface: egui_dock::SurfaceIndex,
+        _node: egui_dock::NodeIndex,
+    ) {
+        if ui.button("Close others").clicked() {
+            ui.close_menu();
+            self.close_tabs = Some(CloseTabs::Others(tab.title.clone()));
         }
-        if filter.is_empty() && filtered_entries.is_empty() {
-            reset_filter(entries, filtered_entries, heights, text_height);
-            filter.clear();
+        if ui.button("Close all").clicked() {
+            ui.close_menu();
+            self.close_tabs = Some(CloseTabs::All);
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
+        if let Some(dashboard) = &tab.dashboard {
+            dashboard.ui(ui, &self.timestamp_format, &self.palette);
+            return;
         }
 
-        let mut current_is_search = is_search.clone();
+        let font = self.log_font(ui);
         let mut current_row = None;
+        let mut picked_pattern = None;
+        let mut events = RowEvents::default();
+        if let Some(selected_date) = self.selected_date {
+            if self.linked_scrolling && tab.synced_date != Some(selected_date) {
+                tab.synced_date = Some(selected_date);
+                current_row = nearest_row(
+                    FilteredEntries::new(&tab.entries, &tab.filtered),
+                    &tab.rows,
+                    selected_date,
+                );
+            }
+        }
         ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
-            ui.horizontal(|ui| {
-                if ui
-                    .button(if *is_search { "Search:" } else { "Filter:" })
-                    .clicked()
-                {
-                    current_is_search = !*is_search;
+            ui.horizontal_wrapped(|ui| {
+                if let Some(row) = self.filter_bar_ui(ui, tab) {
+                    current_row = Some(row);
                 }
-                let mut current_filter = filter.clone();
-                let mut current_levels = tab.enabled_levels.clone();
-                ui.add(egui::TextEdit::singleline(&mut current_filter).desired_width(120.0));
-                if ui.button("ｘ").clicked() {
-                    current_filter.clear();
-                    filter.clear();
-                    reset_filter(entries, filtered_entries, &mut tab.heights, text_height);
+                // Only go through the entries again when something that affects them changed
+                if tab.dirty {
+                    current_row = self.apply_filters(tab, font.height);
                 }
+            });
 
-                ui.separator();
-                ui.label("Levels:");
-                for log_enum in LogLevel::iter() {
-                    if log_enum == LogLevel::Unknown {
-                        continue;
+            let entries = &tab.entries;
+            let filter = &tab.filter;
+            let timestamps = &self.timestamp_format;
+            let start = tab.start;
+            let filtered_entries = FilteredEntries::new(entries, &tab.filtered);
+            let mut status = format!("{} of {} entries", filtered_entries.len(), entries.len());
+            if tab.collapse_duplicates {
+                status += &format!(" in {} rows", tab.rows.len());
+            }
+            if !filter.is_empty() {
+                match tab.current_match_index {
+                    Some(index) => {
+                        status += &format!(", match {} of {}", index + 1, tab.matches.len())
+                    }
+                    None => status += ", no matches",
+                }
+            }
+            ui.label(RichText::new(status).small().weak());
+            if let Some(histogram) = tab
+                .overview
+                .histogram
+                .as_ref()
+                .filter(|_| self.show_histogram)
+            {
+                match histogram.ui(ui, &self.palette) {
+                    Some(HistogramEvent::Clicked(timestamp)) => {
+                        current_row = nearest_row(filtered_entries, &tab.rows, timestamp);
+                    }
+                    Some(HistogramEvent::Selected(first, second)) => {
+                        self.first_date = first;
+                        self.second_date = second;
                     }
-                    let mut enabled = current_levels.contains(&log_enum);
+                    None => (),
+                }
+            }
+            if !tab.service_counts.is_empty() {
+                let counts = tab
+                    .service_counts
+                    .iter()
+                    .map(|(service, count)| format!("{service}: {count}"))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                ui.label(RichText::new(counts).small().weak());
+            }
+            if !tab.bookmarks.is_empty() {
+                egui::CollapsingHeader::new(format!("Bookmarks ({})", tab.bookmarks.len()))
+                    .id_source(("bookmarks", &tab.title))
+                    .show(ui, |ui| {
+                        for bookmark in &tab.bookmarks {
+                            ui.horizontal(|ui| {
+                                if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                                    events.toggled_bookmark = Some(bookmark.clone());
+                                }
+                                let text = format!(
+                                    "{}  {}",
+                                    timestamps.format(bookmark.timestamp, start),
+                                    bookmark.message.lines().next().unwrap_or_default()
+                                );
+                                if ui
+                                    .link(ansi::strip_ansi(&text))
+                                    .on_hover_text("Jump to the entry, if the filters show it")
+                                    .clicked()
+                                {
+                                    if let Some(row) = bookmark.row(filtered_entries, &tab.rows) {
+                                        current_row = Some(row);
+                                    }
+                                }
+                            });
+                        }
+                        if ui.button("Download").clicked() {
+                            let bookmarked = entries
+                                .iter()
+                                .filter(|entry| tab.bookmarks.contains(&EntryId::new(entry)));
+                            export::download_file(
+                                &format!(
+                                    "{} bookmarks.{}",
+                                    tab.title,
+                                    tab.export_format.extension()
+                                ),
+                                export_entries(
+                                    bookmarked,
+                                    &tab.notes,
+                                    tab.export_format,
+                                    tab.strip_ansi,
+                                    timestamps,
+                                    start,
+                                ),
+                            );
+                        }
+                    });
+            }
+
+            if tab.raw {
+                let entry = current_row.map(|row| tab.rows[row].index);
+                raw_ui(
+                    ui,
+                    filtered_entries,
+                    &tab.raw_lines,
+                    &tab.rx,
+                    self.palette.highlight,
+                    &font.font_id,
+                    entry,
+                );
+                return;
+            }
+
+            egui::CollapsingHeader::new("Frequent patterns")
+                .id_source(("patterns", &tab.title))
+                .show(ui, |ui| {
+                    let patterns = tab
+                        .patterns
+                        .get_or_insert_with(|| frequent_patterns(entries));
+                    egui::Grid::new(("patterns", &tab.title))
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (count, pattern) in patterns.iter() {
+                                ui.label(count.to_string());
+                                if ui
+                                    .link(ansi::strip_ansi(pattern))
+                                    .on_hover_text("Filter the tab to the lines of this pattern")
+                                    .clicked()
+                                {
+                                    picked_pattern = Some(pattern.clone());
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
+                if let Some(row) = tab.overview.strip_ui(ui, tab.rows.len(), &self.palette) {
+                    current_row = Some(row);
+                }
+                ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
+                    self.table_ui(ui, tab, &font, current_row, &mut events);
+                });
+            });
+        });
+
+        let chars_per_line = if tab.wrap {
+            events
+                .content_width
+                .map(|width| (width / font.char_width) as usize)
+                .or(tab.chars_per_line)
+        } else {
+            None
+        };
+        let resized = chars_per_line != tab.chars_per_line || font.height != tab.text_height;
+        tab.text_height = font.height;
+        tab.chars_per_line = chars_per_line;
+        if let Some(entry) = events.clicked {
+            dbg!(&entry.timestamp);
+            self.selected_date = Some(entry.timestamp);
+            tab.synced_date = Some(entry.timestamp);
+            self.selected_entry = Some(SelectedEntry {
+                tab: tab.title.clone(),
+                entry,
+            });
+        }
+        if events.editing_note.is_some() {
+            tab.editing_note = events.editing_note;
+        }
+        self.note_window_ui(ui, tab);
+        if let Some(pattern) = picked_pattern {
+            tab.filter = pattern_regex(&pattern);
+            tab.use_regex = true;
+            tab.is_search = false;
+            tab.dirty = true;
+        }
+        if let Some(bookmark) = events.toggled_bookmark {
+            if !tab.bookmarks.remove(&bookmark) {
+                tab.bookmarks.insert(bookmark);
+            }
+        }
+        if let Some(row_index) = events.toggled_row {
+            if !tab.expanded.remove(&row_index) {
+                tab.expanded.insert(row_index);
+            }
+        }
+        if resized || events.toggled_row.is_some() {
+            tab.heights = row_heights(
+                FilteredEntries::new(&tab.entries, &tab.filtered),
+                &tab.rows,
+                &tab.expanded,
+                font.height,
+                tab.chars_per_line,
+            );
+        }
+    }
+}
+
+// Font of the log text, resolved once per frame
+struct LogFont {
+    font_id: egui::FontId,
+    format: TextFormat,
+    height: f32,
+    char_width: f32,
+}
+
+// What the rows of the table asked for, applied once the table is shown
+#[derive(Default)]
+struct RowEvents {
+    clicked: Option<LogEntry>,
+    toggled_row: Option<usize>,
+    toggled_bookmark: Option<EntryId>,
+    editing_note: Option<(EntryId, String)>,
+    content_width: Option<f32>,
+}
+
+impl TabViewer {
+    fn log_font(&self, ui: &egui::Ui) -> LogFont {
+        let font_id = if self.monospace {
+            egui::FontId::monospace(self.font_size)
+        } else {
+            egui::FontId::proportional(self.font_size)
+        };
+        LogFont {
+            format: TextFormat {
+                font_id: font_id.clone(),
+                ..Default::default()
+            },
+            height: font_id.size.max(ui.spacing().interact_size.y),
+            char_width: ui.fonts(|fonts| fonts.glyph_width(&font_id, '0')),
+            font_id,
+        }
+    }
+
+    // Returns the row to scroll to when stepping through the matches, gaps or jumping to a time
+    fn filter_bar_ui(&mut self, ui: &mut egui::Ui, tab: &mut TabContent) -> Option<usize> {
+        let mut current_row = None;
+        let mut current_is_search = tab.is_search;
+        let mut picked_preset = None;
+        if ui
+            .button(if tab.is_search { "Search:" } else { "Filter:" })
+            .clicked()
+        {
+            current_is_search = !tab.is_search;
+        }
+        let mut current_filter = tab.filter.clone();
+        let mut current_levels = tab.enabled_levels.clone();
+        let mut current_components = tab.enabled_components.clone();
+        let mut current_use_regex = tab.use_regex;
+        let mut current_case_sensitive = tab.case_sensitive;
+        let response = ui
+            .add(egui::TextEdit::singleline(&mut current_filter).desired_width(120.0))
+            .on_hover_text(if tab.use_regex {
+                "Regular expression"
+            } else {
+                "Space-separated terms must all match, start with | to match any of them"
+            });
+        if std::mem::take(&mut tab.focus_search) {
+            response.request_focus();
+        }
+        let mut step: Option<isize> = None;
+        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            step = Some(if ui.input(|i| i.modifiers.shift) {
+                -1
+            } else {
+                1
+            });
+            response.request_focus();
+        }
+        if ui
+            .small_button("⬆")
+            .on_hover_text("Previous match")
+            .clicked()
+        {
+            step = Some(-1);
+        }
+        if ui.small_button("⬇").on_hover_text("Next match").clicked() {
+            step = Some(1);
+        }
+        if let Some(step) = step.filter(|_| !tab.matches.is_empty()) {
+            let count = tab.matches.len() as isize;
+            let index = match tab.current_match_index {
+                Some(index) => (index as isize + step).rem_euclid(count) as usize,
+                None => 0,
+            };
+            tab.current_match_index = Some(index);
+            current_row = Some(tab.matches[index]);
+        }
+        if ui.button("ｘ").clicked() || std::mem::take(&mut tab.clear_filter) {
+            current_filter.clear();
+        }
+        if ui
+            .add(
+                egui::TextEdit::singleline(&mut tab.exclude)
+                    .hint_text("exclude")
+                    .desired_width(80.0),
+            )
+            .on_hover_text(if tab.use_regex {
+                "Leave out the entries matching any of these space-separated regular expressions"
+            } else {
+                "Leave out the entries containing any of these space-separated terms"
+            })
+            .changed()
+        {
+            tab.dirty = true;
+        }
+        ui.toggle_value(&mut current_use_regex, ".*")
+            .on_hover_text("Use regular expression");
+        ui.toggle_value(&mut current_case_sensitive, "Aa")
+            .on_hover_text("Match case");
+        if ui
+            .add_enabled(
+                !tab.is_search,
+                egui::DragValue::new(&mut tab.context_lines)
+                    .clamp_range(0..=100)
+                    .prefix("±"),
+            )
+            .on_hover_text("Entries shown grayed out before and after each match")
+            .changed()
+        {
+            tab.dirty = true;
+        }
+        if let Some(error) = &tab.regex_error {
+            ui.label(
+                RichText::new(error.lines().last().unwrap_or("Invalid regex")).color(Color32::RED),
+            )
+            .on_hover_text(error);
+        }
+        if let Some(preset) = self.presets_ui(ui, tab) {
+            picked_preset = Some(preset);
+        }
+
+        ui.separator();
+        ui.label("Levels:");
+        for log_enum in LogLevel::iter() {
+            if log_enum == LogLevel::Unknown {
+                continue;
+            }
+            let mut enabled = current_levels.contains(&log_enum);
+            let label = match tab.level_counts.get(log_enum.clone() as usize) {
+                Some(count) => format!("{log_enum} ({count})"),
+                None => log_enum.to_string(),
+            };
+            let label = RichText::new(label).color(self.palette.levels.get(&log_enum));
+            if ui.add(egui::Checkbox::new(&mut enabled, label)).changed() {
+                if enabled {
+                    current_levels.push(log_enum);
+                } else {
+                    current_levels.retain(|x| *x != log_enum);
+                }
+            }
+        }
+
+        let mut sorted_levels = current_levels.clone();
+        sorted_levels.sort();
+        let threshold = LogLevel::iter()
+            .filter(|level| *level != LogLevel::Unknown)
+            .find(|level| {
+                let mut levels = levels_from(level);
+                levels.sort();
+                levels == sorted_levels
+            });
+        egui::ComboBox::from_id_source(("Minimum level", &tab.title))
+            .width(110.0)
+            .selected_text(match &threshold {
+                Some(LogLevel::Trace) => "All levels".to_string(),
+                Some(level) => format!("{level} and above"),
+                None => "Custom".to_string(),
+            })
+            .show_ui(ui, |ui| {
+                for level in LogLevel::iter().filter(|level| *level != LogLevel::Unknown) {
+                    let text = format!("{level} and above");
                     if ui
-                        .add(egui::Checkbox::new(&mut enabled, log_enum.to_string()))
-                        .changed()
+                        .selectable_label(threshold.as_ref() == Some(&level), text)
+                        .clicked()
                     {
-                        if enabled {
-                            current_levels.push(log_enum);
-                        } else {
-                            current_levels.retain(|x| *x != log_enum);
+                        current_levels = levels_from(&level);
+                    }
+                }
+            });
+
+        if !tab.components.is_empty() {
+            ui.separator();
+            ui.menu_button("Components", |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for component in &tab.components {
+                        let mut enabled = current_components.contains(component);
+                        if ui.checkbox(&mut enabled, component).changed() {
+                            if enabled {
+                                current_components.insert(component.clone());
+                            } else {
+                                current_components.remove(component);
+                            }
                         }
                     }
+                });
+            });
+        }
+
+        ui.separator();
+        self.date_range_ui(ui);
+
+        ui.separator();
+        ui.label("Jump to:");
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut tab.jump_to)
+                .hint_text("timestamp")
+                .desired_width(140.0),
+        );
+        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            match dateparser::parse(&tab.jump_to) {
+                Ok(timestamp) => {
+                    current_row = nearest_row(
+                        FilteredEntries::new(&tab.entries, &tab.filtered),
+                        &tab.rows,
+                        timestamp,
+                    )
+                }
+                Err(e) => log::warn!("Invalid timestamp {}: {:#?}", tab.jump_to, e),
+            }
+        }
+
+        ui.separator();
+        ui.label(format!("Gaps ({}):", tab.gaps.len()));
+        if ui
+            .add(egui::DragValue::new(&mut tab.gap_threshold).suffix("s"))
+            .on_hover_text("Minimum silence of a service to count as a gap")
+            .changed()
+        {
+            tab.gaps = parser::detect_gaps(
+                &tab.entries,
+                chrono::TimeDelta::seconds(tab.gap_threshold as i64),
+            );
+            tab.current_gap_index = None;
+        }
+        let mut gap_step: Option<isize> = None;
+        if ui.small_button("⬆").on_hover_text("Previous gap").clicked() {
+            gap_step = Some(-1);
+        }
+        if ui.small_button("⬇").on_hover_text("Next gap").clicked() {
+            gap_step = Some(1);
+        }
+        if let Some(step) = gap_step.filter(|_| !tab.gaps.is_empty()) {
+            let count = tab.gaps.len() as isize;
+            let index = match tab.current_gap_index {
+                Some(index) => (index as isize + step).rem_euclid(count) as usize,
+                None => 0,
+            };
+            tab.current_gap_index = Some(index);
+            current_row = nearest_row(
+                FilteredEntries::new(&tab.entries, &tab.filtered),
+                &tab.rows,
+                tab.gaps[index].timestamp,
+            );
+        }
+
+        ui.separator();
+        if ui
+            .checkbox(&mut tab.collapse_duplicates, "Collapse duplicates")
+            .on_hover_text("Show consecutive identical entries as a single row")
+            .changed()
+        {
+            tab.dirty = true;
+        }
+        ui.checkbox(&mut tab.wrap, "Wrap")
+            .on_hover_text("Wrap long messages instead of scrolling horizontally");
+        if ui
+            .checkbox(&mut tab.raw, "Raw")
+            .on_hover_text("Show the lines as they were in the file")
+            .changed()
+        {
+            tab.dirty = true;
+        }
+        if ui
+            .checkbox(&mut tab.fields, "Fields")
+            .on_hover_text("Filter on the key=value pairs of structured messages")
+            .changed()
+        {
+            tab.dirty = true;
+        }
+        if tab.fields
+            && ui
+                .add(
+                    egui::TextEdit::singleline(&mut tab.field_filter)
+                        .hint_text("key=value")
+                        .desired_width(120.0),
+                )
+                .on_hover_text(
+                    "Space-separated terms must all match, a key alone only needs the field",
+                )
+                .changed()
+        {
+            tab.dirty = true;
+        }
+
+        ui.separator();
+        egui::ComboBox::from_id_source(("Export format", &tab.title))
+            .width(50.0)
+            .selected_text(tab.export_format.to_string())
+            .show_ui(ui, |ui| {
+                for format in ExportFormat::iter() {
+                    ui.selectable_value(&mut tab.export_format, format, format.to_string());
                 }
+            });
+        ui.checkbox(&mut tab.strip_ansi, "Strip ANSI")
+            .on_hover_text("Remove terminal color codes from exported and copied text");
+        if ui
+            .add_enabled(!tab.filtered.is_empty(), egui::Button::new("Download"))
+            .on_hover_text(format!("Export the {} visible entries", tab.filtered.len()))
+            .clicked()
+        {
+            export::download_file(
+                &format!("{}.{}", tab.title, tab.export_format.extension()),
+                export_entries(
+                    FilteredEntries::new(&tab.entries, &tab.filtered).iter(),
+                    &tab.notes,
+                    tab.export_format,
+                    tab.strip_ansi,
+                    &self.timestamp_format,
+                    tab.start,
+                ),
+            );
+        }
+
+        if current_filter != tab.filter
+            || current_is_search != tab.is_search
+            || current_levels != tab.enabled_levels
+            || current_components != tab.enabled_components
+            || current_use_regex != tab.use_regex
+            || current_case_sensitive != tab.case_sensitive
+            || tab.date_range != (self.first_date, self.second_date)
+        {
+            // Every tab shares the range, so each one compares with what it last applied
+            tab.date_range = (self.first_date, self.second_date);
+            tab.is_search = current_is_search;
+            tab.filter = current_filter;
+            tab.enabled_levels = current_levels;
+            tab.enabled_components = current_components;
+            tab.use_regex = current_use_regex;
+            tab.case_sensitive = current_case_sensitive;
+            tab.dirty = true;
+        }
+        if let Some(preset) = picked_preset {
+            preset.filter.apply(tab);
+            if let Some((first, second)) = preset.date_range {
+                self.first_date = first;
+                self.second_date = second;
+            }
+        }
+        current_row
+    }
 
+    // Applying the picked preset is left to the caller, after the filter bar read the tab
+    fn presets_ui(&mut self, ui: &mut egui::Ui, tab: &TabContent) -> Option<FilterPreset> {
+        let mut picked_preset = None;
+        ui.menu_button("Presets", |ui| {
+            let presets = builtin_presets();
+            for preset in &presets {
+                if ui.button(&preset.name).clicked() {
+                    picked_preset = Some(preset.clone());
+                    ui.close_menu();
+                }
+            }
+            if !self.presets.is_empty() {
                 ui.separator();
-                ui.label("Date range:");
-                let first_date = self.first_date.clone();
-                let second_date = self.second_date.clone();
-                ui.add(egui_extras::DatePickerButton::new(&mut self.first_date).id_source("First"));
+            }
+            let mut removed = None;
+            for (index, preset) in self.presets.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.small_button("✖").on_hover_text("Delete").clicked() {
+                        removed = Some(index);
+                    }
+                    if ui.button(&preset.name).clicked() {
+                        picked_preset = Some(preset.clone());
+                        ui.close_menu();
+                    }
+                });
+            }
+            if let Some(index) = removed {
+                self.presets.remove(index);
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
                 ui.add(
-                    egui_extras::DatePickerButton::new(&mut self.second_date).id_source("Second"),
+                    egui::TextEdit::singleline(&mut self.preset_name)
+                        .hint_text("Name")
+                        .desired_width(120.0),
                 );
-
-                if *current_filter != *filter
-                    || current_is_search != *is_search
-                    || current_levels != tab.enabled_levels
-                    || first_date != self.first_date
-                    || second_date != self.second_date
+                let name = self.preset_name.trim();
+                if ui
+                    .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+                    .on_hover_text(
+                        "Save the filters of this tab, replacing a preset of the same name",
+                    )
+                    .clicked()
                 {
-                    *is_search = current_is_search;
-                    *filter = current_filter;
-                    tab.enabled_levels = current_levels;
-                    if let Ok(user_regex) = regex::RegexBuilder::new(filter)
-                        .case_insensitive(true)
-                        .build()
-                    {
-                        *rx = user_regex;
-                        *filtered_entries = entries
-                            .iter()
-                            .filter(|entry| {
-                                entry.timestamp.date_naive() > self.first_date
-                                    && entry.timestamp.date_naive() < self.second_date
-                            })
-                            .filter(|entry| tab.enabled_levels.contains(&entry.level))
-                            .filter(|entry| {
-                                if *is_search {
-                                    true
-                                } else {
-                                    rx.captures(&entry.message).is_some()
-                                        || rx.captures(&entry.level.to_string()).is_some()
-                                        || rx.captures(&entry.timestamp.to_string()).is_some()
-                                }
-                            })
-                            .map(Clone::clone)
-                            .collect();
-                        tab.heights = filtered_entries
-                            .iter()
-                            .map(|entry| {
-                                (entry.message.lines().count() as f32 * text_height * 0.9)
-                                    .max(text_height)
-                            })
-                            .collect();
-                    }
-
-                    current_row = filtered_entries.iter().rposition(|entry| {
-                        rx.captures(&entry.message).is_some()
-                            || rx.captures(&entry.level.to_string()).is_some()
-                            || rx.captures(&entry.timestamp.to_string()).is_some()
+                    let date_range = self
+                        .preset_date_range
+                        .then_some((self.first_date, self.second_date));
+                    self.presets.retain(|preset| preset.name != name);
+                    self.presets.push(FilterPreset {
+                        name: name.to_string(),
+                        filter: TabFilter::from_tab(tab),
+                        date_range,
                     });
+                    self.preset_name.clear();
+                    ui.close_menu();
                 }
             });
+            ui.checkbox(&mut self.preset_date_range, "With the date range");
+        });
+        picked_preset
+    }
 
-            use egui_extras::{Column, TableBuilder};
+    fn date_range_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("Date range:");
+        // Both ends are inclusive, the end covers the whole minute it shows
+        let mut first_day = self.first_date.date_naive();
+        if ui
+            .add(egui_extras::DatePickerButton::new(&mut first_day).id_source("First"))
+            .changed()
+        {
+            self.first_date = first_day.and_time(self.first_date.time()).and_utc();
+        }
+        if let Some((hour, minute)) = time_of_day_ui(ui, &self.first_date) {
+            self.first_date = self
+                .first_date
+                .date_naive()
+                .and_hms_opt(hour, minute, 0)
+                .unwrap()
+                .and_utc();
+        }
+        ui.label("to");
+        let mut second_day = self.second_date.date_naive();
+        if ui
+            .add(egui_extras::DatePickerButton::new(&mut second_day).id_source("Second"))
+            .changed()
+        {
+            self.second_date = second_day.and_time(self.second_date.time()).and_utc();
+        }
+        if let Some((hour, minute)) = time_of_day_ui(ui, &self.second_date) {
+            self.second_date = self
+                .second_date
+                .date_naive()
+                .and_time(end_of_minute(hour, minute))
+                .and_utc();
+        }
+        if ui
+            .small_button("⟲")
+            .on_hover_text("Show all dates")
+            .clicked()
+        {
+            let default = TabViewer::default();
+            self.first_date = default.first_date;
+            self.second_date = default.second_date;
+        }
+    }
 
-            let available_height = ui.available_height();
-            let mut table = TableBuilder::new(ui)
-                .striped(true)
-                .auto_shrink(false)
-                .resizable(true)
-                .cell_layout(egui::Layout::left_to_right(egui::Align::TOP))
-                .column(Column::auto())
-                .column(Column::auto())
-                .column(Column::auto())
-                .min_scrolled_height(0.0)
-                .max_scroll_height(available_height);
-
-            table = table.sense(egui::Sense::click());
-            if let Some(index) = current_row {
-                table = table.scroll_to_row(index, Some(egui::Align::LEFT));
-            }
-            table
-                .header(20.0, |mut header| {
-                    header.col(|ui| {
-                        ui.strong("Timestamp");
-                    });
-                    header.col(|ui| {
-                        ui.strong("Level");
-                    });
-                    header.col(|ui| {
-                        ui.strong("Content");
-                    });
+    // Goes through the entries again with the filters of the tab, returns the row to scroll to
+    fn apply_filters(&self, tab: &mut TabContent, text_height: f32) -> Option<usize> {
+        tab.dirty = false;
+        let entries = &tab.entries;
+        let filtered = &mut tab.filtered;
+        let had_rows = !tab.rows.is_empty();
+        let query =
+            SearchQuery::parse(&tab.filter, &tab.exclude, tab.use_regex, tab.case_sensitive);
+        tab.regex_error = query.as_ref().err().map(ToString::to_string);
+        if let Ok((user_regex, query)) = query {
+            tab.rx = user_regex;
+            tab.query = query;
+            let context_lines = if tab.is_search || tab.filter.is_empty() {
+                0
+            } else {
+                tab.context_lines
+            };
+            // Only filtering drops entries, searching has to go through all of them,
+            // and so does showing the entries around the matches
+            let indexed = if !tab.is_search && context_lines == 0 && !tab.query.words.is_empty() {
+                let index = tab.index.get_or_insert_with(|| SearchIndex::new(entries));
+                tab.query.candidates(index)
+            } else {
+                None
+            };
+            let field_terms = if tab.fields {
+                field_terms(&tab.field_filter)
+            } else {
+                vec![]
+            };
+            let unmatched: Vec<usize> = entries
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| indexed.as_ref().map_or(true, |indexed| indexed[*index]))
+                .filter(|(_, entry)| {
+                    in_date_range(entry.timestamp, self.first_date, self.second_date)
                 })
-                .body(|body| {
-                    body.heterogeneous_rows(tab.heights.iter().copied(), move |mut row| {
-                        let row_index = row.index();
-                        let entry = &filtered_entries[row_index];
-                        row.col(|ui| {
-                            if filter.is_empty() {
-                                ui.label(&entry.timestamp.to_string());
-                            } else {
-                                let mut job = LayoutJob::default();
-                                highlight_text_in_ui(&entry.timestamp.to_string(), rx, &mut job);
-                                ui.label(job);
-                            }
-                        });
-                        row.col(|ui| {
-                            let color = match entry.level {
-                                parser::LogLevel::Error => Color32::from_hex("#D55E00").unwrap(),
-                                parser::LogLevel::Warn => Color32::from_hex("#E69F00").unwrap(),
-                                parser::LogLevel::Info => Color32::from_hex("#56B4E9").unwrap(),
-                                parser::LogLevel::Debug => Color32::from_hex("#CC79A7").unwrap(),
-                                parser::LogLevel::Trace => Color32::GRAY,
-                                parser::LogLevel::Unknown => Color32::GOLD,
-                            };
-                            ui.label(RichText::new(entry.level.to_string()).color(color));
+                .filter(|(_, entry)| match &entry.component {
+                    Some(component) => tab.enabled_components.contains(component),
+                    None => true,
+                })
+                .filter(|(_, entry)| fields_match(&field_terms, &entry.message))
+                .filter(|(_, entry)| !tab.query.excludes(entry))
+                .map(|(index, _)| index)
+                .collect();
+            let candidates: Vec<usize> = unmatched
+                .iter()
+                .copied()
+                .filter(|&index| tab.is_search || tab.query.matches(&entries[index]))
+                .collect();
+            tab.level_counts = count_levels(candidates.iter().map(|&index| &entries[index]));
+            let enabled = |index: &usize| tab.enabled_levels.contains(&entries[*index].level);
+            *filtered = candidates.into_iter().filter(enabled).collect();
+            (*filtered, tab.context) = if context_lines > 0 {
+                let shown: Vec<usize> = unmatched.into_iter().filter(enabled).collect();
+                with_context(&shown, filtered, context_lines)
+            } else {
+                (std::mem::take(filtered), vec![])
+            };
+            let filtered_entries = FilteredEntries::new(entries, filtered);
+            tab.rows = build_rows(filtered_entries, tab.collapse_duplicates);
+            tab.expanded.clear();
+            tab.heights = row_heights(
+                filtered_entries,
+                &tab.rows,
+                &tab.expanded,
+                text_height,
+                tab.chars_per_line,
+            );
+            tab.overview = Overview::new(filtered_entries, &tab.rows);
+            tab.raw_lines = if tab.raw {
+                raw_lines(filtered_entries)
+            } else {
+                vec![]
+            };
+        }
+
+        let filtered_entries = FilteredEntries::new(entries, filtered);
+        tab.matches = if tab.filter.is_empty() {
+            vec![]
+        } else {
+            tab.rows
+                .iter()
+                .enumerate()
+                .filter(|(_, row)| tab.query.matches(&filtered_entries[row.index]))
+                .map(|(index, _)| index)
+                .collect()
+        };
+        tab.current_match_index = tab.matches.len().checked_sub(1);
+        if tab.filter.is_empty() {
+            // Freshly opened tabs start at the top
+            tab.rows.len().checked_sub(1).filter(|_| had_rows)
+        } else {
+            tab.matches.last().copied()
+        }
+    }
+
+    fn table_ui(
+        &self,
+        ui: &mut egui::Ui,
+        tab: &TabContent,
+        font: &LogFont,
+        scroll_to_row: Option<usize>,
+        events: &mut RowEvents,
+    ) {
+        use egui_extras::{Column, TableBuilder};
+
+        // Keep the resizable column widths per tab, egui persists them by id
+        ui.push_id(egui::Id::new(("log_table", &tab.title)), |ui| {
+            // Plain labels in the table follow the chosen log font too
+            ui.style_mut().override_font_id = Some(font.font_id.clone());
+            egui::ScrollArea::new([!tab.wrap, false])
+                .id_source("horizontal_scroll")
+                .auto_shrink(false)
+                .show(ui, |ui| {
+                    let available_height = ui.available_height();
+                    let mut table = TableBuilder::new(ui)
+                        .striped(true)
+                        .auto_shrink(false)
+                        .resizable(true)
+                        .cell_layout(egui::Layout::left_to_right(egui::Align::TOP))
+                        .columns(
+                            Column::auto(),
+                            3 + tab.show_service as usize
+                                + self.show_source_file as usize
+                                + self.show_deltas as usize,
+                        )
+                        .column(if tab.wrap {
+                            Column::remainder()
+                        } else {
+                            Column::auto()
+                        })
+                        .min_scrolled_height(0.0)
+                        .max_scroll_height(available_height);
+
+                    table = table.sense(egui::Sense::click());
+                    if let Some(index) = scroll_to_row {
+                        table = table.scroll_to_row(index, Some(egui::Align::LEFT));
+                    }
+                    table
+                        .header(20.0, |mut header| self.table_header(&mut header, tab))
+                        .body(|body| {
+                            body.heterogeneous_rows(tab.heights.iter().copied(), |mut row| {
+                                self.row_ui(&mut row, tab, font, events)
+                            });
                         });
+                });
+        });
+    }
+
+    fn table_header(&self, header: &mut egui_extras::TableRow<'_, '_>, tab: &TabContent) {
+        header.col(|ui| {
+            ui.strong("Timestamp");
+        });
+        if self.show_deltas {
+            header.col(|ui| {
+                ui.strong("Δt");
+            });
+        }
+        if tab.show_service {
+            header.col(|ui| {
+                ui.strong("Service");
+            });
+        }
+        if self.show_source_file {
+            header.col(|ui| {
+                ui.strong("File");
+            });
+        }
+        header.col(|ui| {
+            ui.strong("Level");
+        });
+        header.col(|ui| {
+            ui.strong("Component");
+        });
+        header.col(|ui| {
+            ui.strong("Content");
+        });
+    }
+
+    fn row_ui(
+        &self,
+        row: &mut egui_extras::TableRow<'_, '_>,
+        tab: &TabContent,
+        font: &LogFont,
+        events: &mut RowEvents,
+    ) {
+        let filtered_entries = FilteredEntries::new(&tab.entries, &tab.filtered);
+        let row_index = row.index();
+        let Row { index, repeats } = tab.rows[row_index];
+        let entry = &filtered_entries[index];
+        let opacity = if tab.context.get(index) == Some(&true) {
+            CONTEXT_OPACITY
+        } else {
+            1.0
+        };
+        let id = EntryId::new(entry);
+        let note = tab.notes.get(&id);
+        row.col(|ui| {
+            ui.set_opacity(opacity);
+            let bookmarked = tab.bookmarks.contains(&id);
+            let star = if bookmarked {
+                RichText::new("★").color(self.palette.levels.get(&LogLevel::Warn))
+            } else {
+                RichText::new("☆").weak()
+            };
+            if ui
+                .add(egui::Label::new(star).sense(egui::Sense::click()))
+                .on_hover_text(if bookmarked {
+                    "Remove bookmark"
+                } else {
+                    "Bookmark"
+                })
+                .clicked()
+            {
+                events.toggled_bookmark = Some(id.clone());
+            }
+            if let Some(note) = note {
+                if ui
+                    .add(egui::Label::new("🗒").sense(egui::Sense::click()))
+                    .on_hover_text(note)
+                    .clicked()
+                {
+                    events.editing_note = Some((id.clone(), note.clone()));
+                }
+            }
+            if let Some(gap) = find_gap(&tab.gaps, entry) {
+                let color = self.palette.levels.get(&LogLevel::Error);
+                match gap.duration {
+                    Some(duration) => ui
+                        .label(RichText::new("⏸").color(color))
+                        .on_hover_text(format!("No entries for {}", format_delta(duration))),
+                    None => ui
+                        .label(RichText::new("⟲").color(color))
+                        .on_hover_text("Service restarted"),
+                };
+            }
+            let mut timestamp = self.timestamp_format.format(entry.timestamp, tab.start);
+            if repeats > 1 {
+                let last = &filtered_entries[index + repeats - 1];
+                timestamp += "\n";
+                timestamp += &self.timestamp_format.format(last.timestamp, tab.start);
+            }
+            if tab.filter.is_empty() {
+                ui.label(timestamp);
+            } else {
+                let mut job = LayoutJob::default();
+                highlight_text_in_ui(
+                    &timestamp,
+                    &tab.rx,
+                    font.format.clone(),
+                    self.palette.highlight,
+                    &mut job,
+                );
+                ui.label(job);
+            }
+        });
+        if self.show_deltas {
+            row.col(|ui| {
+                ui.set_opacity(opacity);
+                let Some(previous) = row_index.checked_sub(1) else {
+                    return;
+                };
+                let previous = tab.rows[previous];
+                let previous = &filtered_entries[previous.index + previous.repeats - 1];
+                let delta = entry.timestamp - previous.timestamp;
+                let text = RichText::new(format_delta(delta));
+                if delta > chrono::TimeDelta::seconds(LARGE_GAP_SECONDS) {
+                    ui.label(text.color(self.palette.levels.get(&LogLevel::Error)));
+                } else {
+                    ui.label(text.weak());
+                }
+            });
+        }
+        if tab.show_service {
+            row.col(|ui| {
+                ui.set_opacity(opacity);
+                if let Some(service) = &entry.service {
+                    ui.label(service);
+                }
+            });
+        }
+        if self.show_source_file {
+            row.col(|ui| {
+                ui.set_opacity(opacity);
+                if let Some(path) = &entry.source_file {
+                    let name = path.rsplit('/').next().unwrap_or(path);
+                    ui.label(name).on_hover_text(path);
+                }
+            });
+        }
+        row.col(|ui| {
+            ui.set_opacity(opacity);
+            let color = self.palette.levels.get(&entry.level);
+            if tab.filter.is_empty() {
+                ui.label(RichText::new(entry.level.to_string()).color(color));
+            } else {
+                let mut job = LayoutJob::default();
+                highlight_text_in_ui(
+                    &entry.level.to_string(),
+                    &tab.rx,
+                    TextFormat {
+                        color,
+                        ..font.format.clone()
+                    },
+                    self.palette.highlight,
+                    &mut job,
+                );
+                ui.label(job);
+            }
+        });
+
+        row.col(|ui| {
+            ui.set_opacity(opacity);
+            if let Some(component) = &entry.component {
+                if tab.filter.is_empty() {
+                    ui.label(component);
+                } else {
+                    let mut job = LayoutJob::default();
+                    highlight_text_in_ui(
+                        component,
+                        &tab.rx,
+                        font.format.clone(),
+                        self.palette.highlight,
+                        &mut job,
+                    );
+                    ui.label(job);
+                }
+            }
+        });
+
+        row.col(|ui| {
+            ui.set_opacity(opacity);
+            if tab.wrap {
+                events.content_width = Some(ui.available_width());
+            }
+            if repeats > 1 {
+                ui.label(RichText::new(format!("x{repeats}")).strong());
+            }
+            let is_expanded = tab.expanded.contains(&row_index);
+            if entry.lines > 1
+                && ui
+                    .small_button(if is_expanded { "⏷" } else { "⏵" })
+                    .on_hover_text(format!("{} lines", entry.lines))
+                    .clicked()
+            {
+                events.toggled_row = Some(row_index);
+            }
+            self.message_ui(
+                ui,
+                entry,
+                is_expanded,
+                (!tab.filter.is_empty()).then_some(&tab.rx),
+                font,
+                tab.wrap,
+            );
+        });
+
+        let response = row.response();
+        if response.clicked() {
+            dbg!(&entry.timestamp);
+            events.clicked = Some(entry.clone());
+        }
+        response.context_menu(|ui| {
+            if ui
+                .button(if note.is_some() {
+                    "Edit note"
+                } else {
+                    "Add note"
+                })
+                .clicked()
+            {
+                events.editing_note = Some((id.clone(), note.cloned().unwrap_or_default()));
+                ui.close_menu();
+            }
+            ui.separator();
+            let copied_text = if ui.button("Copy line").clicked() {
+                let entry = LogEntry {
+                    note: note.cloned(),
+                    ..entry.clone()
+                };
+                Some(export::entry_to_text(
+                    &entry,
+                    &self.timestamp_format,
+                    tab.start,
+                ))
+            } else if ui.button("Copy message").clicked() {
+                Some(entry.message.clone())
+            } else if entry.traceback.is_some() && ui.button("Copy traceback").clicked() {
+                entry.traceback.clone()
+            } else if ui.button("Copy timestamp").clicked() {
+                Some(self.timestamp_format.format(entry.timestamp, tab.start))
+            } else {
+                None
+            };
+            if let Some(mut copied_text) = copied_text {
+                if tab.strip_ansi {
+                    copied_text = ansi::strip_ansi(&copied_text);
+                }
+                ui.output_mut(|o| o.copied_text = copied_text);
+                ui.close_menu();
+            }
+        });
+    }
+
+    // Message cell of a table row, with the traceback below it when expanded and its exception after
+    // it when collapsed
+    fn message_ui(
+        &self,
+        ui: &mut egui::Ui,
+        entry: &LogEntry,
+        is_expanded: bool,
+        highlight: Option<&regex::Regex>,
+        font: &LogFont,
+        wrap: bool,
+    ) {
+        let message = if is_expanded {
+            entry.message.as_str()
+        } else {
+            entry.message.lines().next().unwrap_or_default()
+        };
+        let mut job = LayoutJob::default();
+        match highlight {
+            Some(rx) => highlight_text_in_ui(
+                message,
+                rx,
+                font.format.clone(),
+                self.palette.highlight,
+                &mut job,
+            ),
+            None => create_layout_from_terminal_escape_sequence(
+                message,
+                &font.format,
+                ui.visuals().strong_text_color(),
+                &mut job,
+            ),
+        }
+        let monospace = egui::FontId::monospace(font.font_id.size);
+        match &entry.traceback {
+            Some(traceback) if is_expanded => {
+                ui.vertical(|ui| {
+                    ui.add(egui::Label::new(job).wrap(wrap));
+                    ui.add(
+                        egui::Label::new(RichText::new(traceback).font(monospace).weak())
+                            .wrap(wrap),
+                    );
+                });
+            }
+            _ => {
+                // The exception summarizes a collapsed traceback
+                if let Some(exception) = entry.exception() {
+                    job.append(
+                        exception,
+                        font.char_width * 2.0,
+                        TextFormat {
+                            font_id: monospace,
+                            color: self.palette.levels.get(&LogLevel::Error),
+                            ..Default::default()
+                        },
+                    );
+                }
+                ui.add(egui::Label::new(job).wrap(wrap));
+            }
+        }
+    }
+
+    fn note_window_ui(&self, ui: &egui::Ui, tab: &mut TabContent) {
+        let Some((id, mut text)) = tab.editing_note.take() else {
+            return;
+        };
+        let mut open = true;
+        let mut done = false;
+        egui::Window::new("Note")
+            .id(egui::Id::new(("note", &tab.title)))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ui.ctx(), |ui| {
+                ui.label(
+                    RichText::new(format!(
+                        "{}  {}",
+                        self.timestamp_format.format(id.timestamp, tab.start),
+                        id.message.lines().next().unwrap_or_default()
+                    ))
+                    .weak(),
+                );
+                ui.add(egui::TextEdit::multiline(&mut text).desired_rows(3));
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        if text.trim().is_empty() {
+                            tab.notes.remove(&id);
+                        } else {
+                            tab.notes.insert(id.clone(), text.clone());
+                        }
+                        done = true;
+                    }
+                    if tab.notes.contains_key(&id) && ui.button("Delete").clicked() {
+                        tab.notes.remove(&id);
+                        done = true;
+                    }
+                });
+            });
+        if open && !done {
+            tab.editing_note = Some((id, text));
+        }
+    }
+}
+
+// Gaps between rows longer than this are highlighted in the delta column
+const LARGE_GAP_SECONDS: i64 = 60;
+
+fn format_delta(delta: chrono::TimeDelta) -> String {
+    let Ok(delta) = delta.to_std() else {
+        return "-".into();
+    };
+    if delta.as_secs() < 10 {
+        format!("+{:.3}s", delta.as_secs_f64())
+    } else {
+        let seconds = std::time::Duration::from_secs(delta.as_secs());
+        format!("+{}", humantime::format_duration(seconds))
+    }
+}
+
+fn find_gap<'a>(gaps: &'a [parser::Gap], entry: &LogEntry) -> Option<&'a parser::Gap> {
+    let start = gaps.partition_point(|gap| gap.timestamp < entry.timestamp);
+    gaps[start..]
+        .iter()
+        .take_while(|gap| gap.timestamp == entry.timestamp)
+        .find(|gap| gap.service == entry.service)
+}
+
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+struct LevelColors {
+    error: Color32,
+    warn: Color32,
+    info: Color32,
+    debug: Color32,
+    trace: Color32,
+    unknown: Color32,
+}
+
+impl LevelColors {
+    fn get(&self, level: &LogLevel) -> Color32 {
+        match level {
+            LogLevel::Error => self.error,
+            LogLevel::Warn => self.warn,
+            LogLevel::Info => self.info,
+            LogLevel::Debug => self.debug,
+            LogLevel::Trace => self.trace,
+            LogLevel::Unknown => self.unknown,
+        }
+    }
+
+    fn get_mut(&mut self, level: &LogLevel) -> &mut Color32 {
+        match level {
+            LogLevel::Error => &mut self.error,
+            LogLevel::Warn => &mut self.warn,
+            LogLevel::Info => &mut self.info,
+            LogLevel::Debug => &mut self.debug,
+            LogLevel::Trace => &mut self.trace,
+            LogLevel::Unknown => &mut self.unknown,
+        }
+    }
+}
+
+// Colors of the log content, the visuals of the widgets come from egui
+#[derive(Clone, Copy)]
+struct Palette {
+    levels: LevelColors,
+    // Background of search matches
+    highlight: Color32,
+    // Entries found in only the right side of a diff
+    added: Color32,
+}
+
+impl Palette {
+    // Okabe-Ito colors, told apart with most color vision deficiencies
+    const DEFAULT: Palette = Palette {
+        levels: LevelColors {
+            error: Color32::from_rgb(0xD5, 0x5E, 0x00),
+            warn: Color32::from_rgb(0xE6, 0x9F, 0x00),
+            info: Color32::from_rgb(0x56, 0xB4, 0xE9),
+            debug: Color32::from_rgb(0xCC, 0x79, 0xA7),
+            trace: Color32::GRAY,
+            unknown: Color32::GOLD,
+        },
+        highlight: Color32::from_rgb(0xE6, 0x9F, 0x00),
+        added: Color32::from_rgb(0x00, 0x9E, 0x73),
+    };
+
+    // Warnings and errors differ in lightness too, not only in hue, and every color keeps a
+    // contrast ratio of at least 4.5 with the panel background of its egui theme
+    const COLORBLIND_DARK: Palette = Palette {
+        levels: LevelColors {
+            error: Color32::from_rgb(0xFF, 0x7F, 0x50),
+            warn: Color32::from_rgb(0xF0, 0xE4, 0x42),
+            info: Color32::from_rgb(0x56, 0xB4, 0xE9),
+            debug: Color32::from_rgb(0xCC, 0x79, 0xA7),
+            trace: Color32::from_rgb(0xBB, 0xBB, 0xBB),
+            unknown: Color32::from_rgb(0x00, 0x9E, 0x73),
+        },
+        highlight: Color32::from_rgb(0xF0, 0xE4, 0x42),
+        added: Color32::from_rgb(0x2E, 0xC4, 0xA0),
+    };
+
+    const COLORBLIND_LIGHT: Palette = Palette {
+        levels: LevelColors {
+            error: Color32::from_rgb(0xB3, 0x47, 0x00),
+            warn: Color32::from_rgb(0x8A, 0x6D, 0x00),
+            info: Color32::from_rgb(0x00, 0x72, 0xB2),
+            debug: Color32::from_rgb(0xA0, 0x45, 0x7A),
+            trace: Color32::from_rgb(0x55, 0x55, 0x55),
+            unknown: Color32::from_rgb(0x00, 0x7A, 0x5A),
+        },
+        highlight: Color32::from_rgb(0xF0, 0xE4, 0x42),
+        added: Color32::from_rgb(0x00, 0x7A, 0x5A),
+    };
+
+    const HIGH_CONTRAST_DARK: Palette = Palette {
+        levels: LevelColors {
+            error: Color32::from_rgb(0xFF, 0x40, 0x40),
+            warn: Color32::from_rgb(0xFF, 0xD0, 0x00),
+            info: Color32::from_rgb(0x40, 0xC8, 0xFF),
+            debug: Color32::from_rgb(0xFF, 0x70, 0xFF),
+            trace: Color32::from_rgb(0xE0, 0xE0, 0xE0),
+            unknown: Color32::from_rgb(0xFF, 0xE0, 0x60),
+        },
+        highlight: Color32::from_rgb(0xFF, 0xD0, 0x00),
+        added: Color32::from_rgb(0x40, 0xFF, 0x80),
+    };
+
+    const HIGH_CONTRAST_LIGHT: Palette = Palette {
+        levels: LevelColors {
+            error: Color32::from_rgb(0xB0, 0x00, 0x00),
+            warn: Color32::from_rgb(0x8A, 0x4B, 0x00),
+            info: Color32::from_rgb(0x00, 0x50, 0xA0),
+            debug: Color32::from_rgb(0x8B, 0x00, 0x8B),
+            trace: Color32::from_rgb(0x40, 0x40, 0x40),
+            unknown: Color32::from_rgb(0x6B, 0x5B, 0x00),
+        },
+        highlight: Color32::from_rgb(0xFF, 0xD0, 0x00),
+        added: Color32::from_rgb(0x00, 0x70, 0x20),
+    };
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, EnumIter, PartialEq, serde::Deserialize, serde::Serialize,
+)]
+enum PaletteKind {
+    #[default]
+    Standard,
+    Colorblind,
+    HighContrast,
+}
+
+impl std::fmt::Display for PaletteKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaletteKind::Standard => write!(f, "Standard"),
+            PaletteKind::Colorblind => write!(f, "Colorblind safe"),
+            PaletteKind::HighContrast => write!(f, "High contrast"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct Theme {
+    dark_mode: bool,
+    palette: PaletteKind,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            dark_mode: true,
+            palette: Default::default(),
+        }
+    }
+}
+
+impl Theme {
+    fn palette(&self) -> Palette {
+        match (self.palette, self.dark_mode) {
+            (PaletteKind::Standard, _) => Palette::DEFAULT,
+            (PaletteKind::Colorblind, true) => Palette::COLORBLIND_DARK,
+            (PaletteKind::Colorblind, false) => Palette::COLORBLIND_LIGHT,
+            (PaletteKind::HighContrast, true) => Palette::HIGH_CONTRAST_DARK,
+            (PaletteKind::HighContrast, false) => Palette::HIGH_CONTRAST_LIGHT,
+        }
+    }
+
+    fn visuals(&self) -> egui::Visuals {
+        let mut visuals = if self.dark_mode {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        };
+        if self.palette == PaletteKind::HighContrast {
+            let (text, background) = if self.dark_mode {
+                (Color32::WHITE, Color32::BLACK)
+            } else {
+                (Color32::BLACK, Color32::WHITE)
+            };
+            visuals.override_text_color = Some(text);
+            visuals.panel_fill = background;
+            visuals.window_fill = background;
+            visuals.extreme_bg_color = background;
+            visuals.widgets.noninteractive.bg_stroke.color = text;
+        }
+        visuals
+    }
+
+    // Custom level colors replace those of the palette
+    fn apply(&self, ctx: &egui::Context, level_colors: Option<&LevelColors>) -> Palette {
+        let visuals = self.visuals();
+        if ctx.style().visuals != visuals {
+            ctx.set_visuals(visuals);
+        }
+        let mut palette = self.palette();
+        if let Some(level_colors) = level_colors {
+            palette.levels = *level_colors;
+        }
+        palette
+    }
+}
+
+// Known levels at least as severe as the given one
+fn levels_from(minimum: &LogLevel) -> Vec<LogLevel> {
+    LogLevel::iter()
+        .filter(|level| *level != LogLevel::Unknown && level >= minimum)
+        .collect()
+}
+
+// Number of entries per level, indexed by the level discriminant
+fn count_levels<'a>(entries: impl IntoIterator<Item = &'a LogEntry>) -> Vec<usize> {
+    let mut counts = vec![0; LogLevel::iter().count()];
+    for entry in entries {
+        counts[entry.level.clone() as usize] += 1;
+    }
+    counts
+}
+
+#[derive(Default)]
+struct SearchQuery {
+    terms: Vec<regex::Regex>,
+    // Plain text terms, lowercased, used to look candidates up in the search index
+    words: Vec<String>,
+    any: bool,
+    // Entries matching any of these are left out
+    excluded: Vec<regex::Regex>,
+}
+
+impl SearchQuery {
+    // Returns the regex used to highlight matches together with the query used to filter entries
+    fn parse(
+        filter: &str,
+        exclude: &str,
+        use_regex: bool,
+        case_sensitive: bool,
+    ) -> Result<(regex::Regex, Self), regex::Error> {
+        let build = |pattern: &str| {
+            regex::RegexBuilder::new(pattern)
+                .case_insensitive(!case_sensitive)
+                .build()
+        };
+        let excluded = exclude
+            .split_whitespace()
+            .map(|term| {
+                if use_regex {
+                    build(term)
+                } else {
+                    build(&regex::escape(term))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        if use_regex {
+            let rx = build(filter)?;
+            let query = Self {
+                terms: vec![rx.clone()],
+                words: vec![],
+                any: false,
+                excluded,
+            };
+            return Ok((rx, query));
+        }
+
+        let (any, filter) = match filter.trim_start().strip_prefix('|') {
+            Some(filter) => (true, filter),
+            None => (false, filter),
+        };
+        let words: Vec<String> = filter.split_whitespace().map(str::to_lowercase).collect();
+        let terms: Vec<String> = filter.split_whitespace().map(regex::escape).collect();
+        let rx = build(&terms.join("|"))?;
+        let terms = terms
+            .iter()
+            .map(|term| build(term))
+            .collect::<Result<_, _>>()?;
+        Ok((
+            rx,
+            Self {
+                terms,
+                words,
+                any,
+                excluded,
+            },
+        ))
+    }
+
+    // Entries that may match the query, none when the index can't narrow them down
+    fn candidates(&self, index: &SearchIndex) -> Option<Vec<bool>> {
+        let mut candidates: Option<Vec<bool>> = None;
+        for word in &self.words {
+            let Some(found) = index.lookup(word) else {
+                if self.any {
+                    return None;
+                }
+                continue;
+            };
+            candidates = Some(match candidates {
+                None => found,
+                Some(candidates) => candidates
+                    .into_iter()
+                    .zip(found)
+                    .map(|(a, b)| if self.any { a || b } else { a && b })
+                    .collect(),
+            });
+        }
+        candidates
+    }
+
+    fn excludes(&self, entry: &LogEntry) -> bool {
+        self.excluded.iter().any(|rx| entry_matches(rx, entry))
+    }
+
+    fn matches(&self, entry: &LogEntry) -> bool {
+        if self.terms.is_empty() {
+            return true;
+        }
+        if self.any {
+            self.terms.iter().any(|rx| entry_matches(rx, entry))
+        } else {
+            self.terms.iter().all(|rx| entry_matches(rx, entry))
+        }
+    }
+}
+
+// Inverted index from the lowercase words of the entries to the entries containing them
+struct SearchIndex {
+    size: usize,
+    words: HashMap<String, Vec<usize>>,
+}
+
+impl SearchIndex {
+    fn new(entries: &[LogEntry]) -> Self {
+        let mut words: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let level = entry.level.to_string();
+            let texts = [
+                Some(entry.message.as_str()),
+                Some(level.as_str()),
+                entry.component.as_deref(),
+                entry.traceback.as_deref(),
+            ];
+            for text in texts.into_iter().flatten() {
+                for word in text
+                    .split(|c: char| !is_word_char(c))
+                    .filter(|word| !word.is_empty())
+                {
+                    let entries = words.entry(word.to_lowercase()).or_default();
+                    if entries.last() != Some(&index) {
+                        entries.push(index);
+                    }
+                }
+            }
+        }
+        Self {
+            size: entries.len(),
+            words,
+        }
+    }
+
+    // Terms can match inside words, so every word containing the term is a candidate.
+    // Terms with digits or found in its "UTC" may match the timestamp, which is not indexed.
+    fn lookup(&self, term: &str) -> Option<Vec<bool>> {
+        if !term.chars().all(|c| is_word_char(c) && !c.is_ascii_digit())
+            || TIMESTAMP_WORD.contains(term)
+        {
+            return None;
+        }
+        let mut found = vec![false; self.size];
+        for (_, entries) in self.words.iter().filter(|(word, _)| word.contains(term)) {
+            for &index in entries {
+                found[index] = true;
+            }
+        }
+        Some(found)
+    }
+}
+
+// Only word of the timestamps the filters match, as in "2024-03-12 10:00:00 UTC"
+const TIMESTAMP_WORD: &str = "utc";
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn entry_matches(rx: &regex::Regex, entry: &LogEntry) -> bool {
+    rx.is_match(&entry.message)
+        || entry
+            .traceback
+            .as_ref()
+            .is_some_and(|traceback| rx.is_match(traceback))
+        || rx.is_match(&entry.level.to_string())
+        || entry
+            .component
+            .as_ref()
+            .is_some_and(|component| rx.is_match(component))
+        || rx.is_match(&entry.timestamp.to_string())
+}
+
+const HISTOGRAM_BUCKETS: usize = 120;
+
+// Summaries of the visible rows, rebuilt together with them
+#[derive(Default)]
+struct Overview {
+    histogram: Option<Histogram>,
+    // Rows with warnings or errors, shown in the strip next to the table
+    markers: Vec<(usize, LogLevel)>,
+}
+
+impl Overview {
+    fn new(entries: FilteredEntries<'_>, rows: &[Row]) -> Self {
+        let markers = rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| (index, &entries[row.index].level))
+            .filter(|(_, level)| **level >= LogLevel::Warn)
+            .map(|(index, level)| (index, level.clone()))
+            .collect();
+        Self {
+            histogram: Histogram::new(entries),
+            markers,
+        }
+    }
+
+    // Minimap of the warnings and errors, returns the row that was clicked
+    fn strip_ui(&self, ui: &mut egui::Ui, row_count: usize, palette: &Palette) -> Option<usize> {
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(10.0, ui.available_height()),
+            egui::Sense::click(),
+        );
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+        if row_count == 0 {
+            return None;
+        }
+
+        // Keep the most severe level of each pixel, drawing every marker would be too slow
+        let mut pixels: Vec<Option<&LogLevel>> = vec![None; rect.height().max(1.0) as usize];
+        let pixel_count = pixels.len();
+        for (row, level) in &self.markers {
+            let pixel = (*row as f32 / row_count as f32 * pixel_count as f32) as usize;
+            let pixel = &mut pixels[pixel.min(pixel_count - 1)];
+            if pixel.map_or(true, |other| level > other) {
+                *pixel = Some(level);
+            }
+        }
+        for (y, level) in pixels.iter().enumerate() {
+            if let Some(level) = level {
+                let top = rect.top() + y as f32;
+                painter.rect_filled(
+                    egui::Rect::from_min_max(
+                        egui::pos2(rect.left(), top),
+                        egui::pos2(rect.right(), top + 2.0),
+                    ),
+                    0.0,
+                    palette.levels.get(level),
+                );
+            }
+        }
+
+        let position = response
+            .interact_pointer_pos()
+            .filter(|_| response.clicked())?;
+        let row = (position.y - rect.top()) / rect.height() * row_count as f32;
+        Some((row.max(0.0) as usize).min(row_count - 1))
+    }
+}
+
+// Hour and minute inputs for a bound of the date range, returns the new values when edited
+fn time_of_day_ui(ui: &mut egui::Ui, bound: &DateTime<Utc>) -> Option<(u32, u32)> {
+    let (mut hour, mut minute) = (bound.hour(), bound.minute());
+    let two_digits = |value: f64, _| format!("{value:02}");
+    let mut changed = ui
+        .add(
+            egui::DragValue::new(&mut hour)
+                .clamp_range(0..=23)
+                .custom_formatter(two_digits),
+        )
+        .changed();
+    ui.label(":");
+    changed |= ui
+        .add(
+            egui::DragValue::new(&mut minute)
+                .clamp_range(0..=59)
+                .custom_formatter(two_digits),
+        )
+        .changed();
+    changed.then_some((hour, minute))
+}
+
+// Last instant of the minute, so entries with sub-millisecond timestamps stay inside inclusive bounds
+fn end_of_minute(hour: u32, minute: u32) -> chrono::NaiveTime {
+    chrono::NaiveTime::from_hms_nano_opt(hour, minute, 59, 999_999_999).unwrap()
+}
+
+// Both bounds are inclusive, see `end_of_minute`
+fn in_date_range(timestamp: DateTime<Utc>, first: DateTime<Utc>, second: DateTime<Utc>) -> bool {
+    first <= timestamp && timestamp <= second
+}
+
+enum HistogramEvent {
+    // Start of the clicked bucket
+    Clicked(DateTime<Utc>),
+    // Time range covered by the buckets dragged across
+    Selected(DateTime<Utc>, DateTime<Utc>),
+}
+
+// Entries per level over time, in equally sized time buckets
+struct Histogram {
+    start: DateTime<Utc>,
+    bucket: chrono::TimeDelta,
+    counts: Vec<Vec<usize>>,
+}
+
+impl Histogram {
+    fn new(entries: FilteredEntries<'_>) -> Option<Self> {
+        let start = entries.first()?.timestamp;
+        let span = entries.last()?.timestamp - start;
+        let bucket = (span / HISTOGRAM_BUCKETS as i32).max(chrono::TimeDelta::milliseconds(1));
+        let mut counts = vec![vec![0; LogLevel::iter().count()]; HISTOGRAM_BUCKETS];
+        for entry in entries.iter() {
+            let index = (entry.timestamp - start).num_milliseconds() / bucket.num_milliseconds();
+            counts[(index as usize).min(HISTOGRAM_BUCKETS - 1)][entry.level.clone() as usize] += 1;
+        }
+        Some(Self {
+            start,
+            bucket,
+            counts,
+        })
+    }
+
+    fn ui(&self, ui: &mut egui::Ui, palette: &Palette) -> Option<HistogramEvent> {
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), 40.0),
+            egui::Sense::click_and_drag(),
+        );
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+        let max = self
+            .counts
+            .iter()
+            .map(|bucket| bucket.iter().sum::<usize>())
+            .max()
+            .unwrap_or_default()
+            .max(1);
+        let width = rect.width() / self.counts.len() as f32;
+        for (index, bucket) in self.counts.iter().enumerate() {
+            let left = rect.left() + index as f32 * width;
+            let mut bottom = rect.bottom();
+            // Stack the most severe levels at the bottom
+            for level in LogLevel::iter() {
+                let height = bucket[level.clone() as usize] as f32 / max as f32 * rect.height();
+                if height > 0.0 {
+                    painter.rect_filled(
+                        egui::Rect::from_min_max(
+                            egui::pos2(left, bottom - height),
+                            egui::pos2(left + width, bottom),
+                        ),
+                        0.0,
+                        palette.levels.get(&level),
+                    );
+                }
+                bottom -= height;
+            }
+        }
+
+        let bucket_at = |x: f32| {
+            let index = ((x - rect.left()) / width).max(0.0) as usize;
+            index.min(self.counts.len() - 1)
+        };
+        let response = match response.hover_pos() {
+            Some(position) => {
+                let index = bucket_at(position.x);
+                let counts = LogLevel::iter()
+                    .filter_map(|level| {
+                        let count = self.counts[index][level.clone() as usize];
+                        (count > 0).then(|| format!("{level}: {count}"))
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                response.on_hover_text(format!(
+                    "{}\n{counts}",
+                    self.start + self.bucket * index as i32
+                ))
+            }
+            None => response,
+        };
+        let bucket_start = |index: usize| self.start + self.bucket * index as i32;
+
+        let drag_id = response.id.with("drag_start");
+        if response.drag_started() {
+            if let Some(position) = response.interact_pointer_pos() {
+                ui.data_mut(|data| data.insert_temp(drag_id, position.x));
+            }
+        }
+        if let Some(drag_start) = ui.data(|data| data.get_temp::<f32>(drag_id)) {
+            let pointer = ui.input(|i| i.pointer.latest_pos());
+            let drag_end = pointer.map_or(drag_start, |pointer| pointer.x);
+            let (first, second) = (
+                bucket_at(drag_start.min(drag_end)),
+                bucket_at(drag_start.max(drag_end)),
+            );
+            painter.rect_filled(
+                egui::Rect::from_x_y_ranges(
+                    rect.left() + first as f32 * width..=rect.left() + (second + 1) as f32 * width,
+                    rect.y_range(),
+                ),
+                0.0,
+                ui.visuals().selection.bg_fill.gamma_multiply(0.4),
+            );
+            if response.drag_stopped() {
+                ui.data_mut(|data| data.remove::<f32>(drag_id));
+                return Some(HistogramEvent::Selected(
+                    bucket_start(first),
+                    bucket_start(second + 1) - chrono::TimeDelta::nanoseconds(1),
+                ));
+            }
+        }
+
+        let position = response
+            .interact_pointer_pos()
+            .filter(|_| response.clicked())?;
+        Some(HistogramEvent::Clicked(bucket_start(bucket_at(position.x))))
+    }
+}
+
+// A table row, covering `repeats` consecutive identical entries starting at `index`
+#[derive(Clone, Copy)]
+struct Row {
+    index: usize,
+    repeats: usize,
+}
+
+// Entries left by the filters, looked up in the entries of the tab instead of copied
+#[derive(Clone, Copy)]
+struct FilteredEntries<'a> {
+    entries: &'a [LogEntry],
+    indices: &'a [usize],
+}
+
+impl<'a> FilteredEntries<'a> {
+    fn new(entries: &'a [LogEntry], indices: &'a [usize]) -> Self {
+        Self { entries, indices }
+    }
+
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn first(&self) -> Option<&'a LogEntry> {
+        self.indices.first().map(|&index| &self.entries[index])
+    }
+
+    fn last(&self) -> Option<&'a LogEntry> {
+        self.indices.last().map(|&index| &self.entries[index])
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &'a LogEntry> + 'a {
+        let entries = self.entries;
+        self.indices.iter().map(move |&index| &entries[index])
+    }
+}
+
+impl std::ops::Index<usize> for FilteredEntries<'_> {
+    type Output = LogEntry;
+
+    fn index(&self, index: usize) -> &LogEntry {
+        &self.entries[self.indices[index]]
+    }
+}
+
+fn build_rows(entries: FilteredEntries<'_>, collapse_duplicates: bool) -> Vec<Row> {
+    let mut rows: Vec<Row> = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        if collapse_duplicates {
+            if let Some(last) = rows.last_mut() {
+                let first = &entries[last.index];
+                if first.level == entry.level
+                    && first.message == entry.message
+                    && first.service == entry.service
+                {
+                    last.repeats += 1;
+                    continue;
+                }
+            }
+        }
+        rows.push(Row { index, repeats: 1 });
+    }
+    rows
+}
+
+// Collapsed multi-line messages only take the height of their first line,
+// wrapped lines are estimated from their length
+fn row_heights(
+    entries: FilteredEntries<'_>,
+    rows: &[Row],
+    expanded: &BTreeSet<usize>,
+    text_height: f32,
+    chars_per_line: Option<usize>,
+) -> Vec<f32> {
+    let wrapped = |length: usize| match chars_per_line {
+        Some(chars) => length.div_ceil(chars.max(1)).max(1),
+        None => 1,
+    };
+    rows.iter()
+        .enumerate()
+        .map(|(row_index, row)| {
+            let entry = &entries[row.index];
+            let lines = if expanded.contains(&row_index) {
+                match chars_per_line {
+                    Some(_) => entry
+                        .message
+                        .lines()
+                        .chain(
+                            entry
+                                .traceback
+                                .iter()
+                                .flat_map(|traceback| traceback.lines()),
+                        )
+                        .map(|line| wrapped(line.chars().count()))
+                        .sum(),
+                    None => entry.lines,
+                }
+            } else {
+                let first_line = entry.message.lines().next().unwrap_or_default();
+                wrapped(first_line.chars().count() + entry.exception().map_or(0, str::len))
+            };
+            let lines = if row.repeats > 1 { lines.max(2) } else { lines };
+            (lines as f32 * text_height * 0.9).max(text_height)
+        })
+        .collect()
+}
+
+// Notes by bundle hash and tab title
+type SavedNotes = BTreeMap<u64, BTreeMap<String, Vec<(EntryId, String)>>>;
+
+// Identifies an entry regardless of the filters, by its timestamp and message
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd, serde::Deserialize, serde::Serialize)]
+struct EntryId {
+    timestamp: DateTime<Utc>,
+    message: String,
+}
+
+impl EntryId {
+    fn new(entry: &LogEntry) -> Self {
+        Self {
+            timestamp: entry.timestamp,
+            message: entry.message.clone(),
+        }
+    }
+
+    // Row showing the entry, if it passes the filters
+    fn row(&self, entries: FilteredEntries<'_>, rows: &[Row]) -> Option<usize> {
+        let index = entries
+            .iter()
+            .position(|entry| entry.timestamp == self.timestamp && entry.message == self.message)?;
+        Some(rows.partition_point(|row| row.index + row.repeats <= index))
+    }
+}
+
+fn with_notes<'a>(
+    entries: impl IntoIterator<Item = &'a LogEntry>,
+    notes: &BTreeMap<EntryId, String>,
+) -> Vec<LogEntry> {
+    entries
+        .into_iter()
+        .map(|entry| LogEntry {
+            note: notes.get(&EntryId::new(entry)).cloned(),
+            ..entry.clone()
+        })
+        .collect()
+}
+
+// Downloaded file of the entries, with the notes of the tab
+fn export_entries<'a>(
+    entries: impl IntoIterator<Item = &'a LogEntry>,
+    notes: &BTreeMap<EntryId, String>,
+    format: ExportFormat,
+    strip_ansi: bool,
+    timestamps: &TimestampFormat,
+    start: DateTime<Utc>,
+) -> Vec<u8> {
+    format
+        .export(&with_notes(entries, notes), strip_ansi, timestamps, start)
+        .into_bytes()
+}
+
+// Recognizes a bundle when it is loaded again, from its services and the ends of their logs.
+// FNV-1a over fixed width fields, the saved notes must keep matching across builds and platforms
+fn bundle_hash(logbook: &parser::LogBook) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut write = |bytes: &[u8]| {
+        for byte in bytes {
+            hash = (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+    for (service, entries) in logbook {
+        write(&(service.len() as u64).to_le_bytes());
+        write(service.as_bytes());
+        write(&(entries.len() as u64).to_le_bytes());
+        for entry in entries.first().into_iter().chain(entries.last()) {
+            write(&entry.timestamp.timestamp_micros().to_le_bytes());
+            write(&(entry.message.len() as u64).to_le_bytes());
+            write(entry.message.as_bytes());
+        }
+    }
+    hash
+}
+
+fn nearest_row(
+    entries: FilteredEntries<'_>,
+    rows: &[Row],
+    timestamp: DateTime<Utc>,
+) -> Option<usize> {
+    if rows.is_empty() {
+        return None;
+    }
+    let index = rows.partition_point(|row| entries[row.index].timestamp < timestamp);
+    if index == 0 {
+        return Some(0);
+    }
+    if index == rows.len() {
+        return Some(index - 1);
+    }
+    let before = timestamp - entries[rows[index - 1].index].timestamp;
+    let after = entries[rows[index].index].timestamp - timestamp;
+    Some(if before <= after { index - 1 } else { index })
+}
+
+// A loaded bundle, several can be open to compare them
+struct Bundle {
+    name: String,
+    logs: parser::Processed,
+    // Whether lines before the first entry of a file were kept when loading
+    kept_unparsed: bool,
+    // Identifies the bundle to keep its notes, see `bundle_hash`
+    hash: u64,
+    selected_services: BTreeSet<String>,
+    // Single log file read again as it grows
+    follow: Option<Follow>,
+    // Vehicle streaming its logs, see `live::SERVICE`
+    live: Option<live::Live>,
+    // Time of the last read of the followed file or the stream, see `egui::InputState::time`
+    read_at: f64,
+}
+
+// The stream ends with its bundle
+impl Drop for Bundle {
+    fn drop(&mut self) {
+        if let Some(live) = &self.live {
+            live.disconnect();
+        }
+    }
+}
+
+// Only followed on native, where files are read from disk
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+struct Follow {
+    path: std::path::PathBuf,
+    service: String,
+    // Where the lines not read yet start
+    offset: u64,
+    enabled: bool,
+}
+
+impl Bundle {
+    // With several bundles open, tabs are told apart by the bundle name
+    fn tab_title(&self, title: &str, several: bool) -> String {
+        if several {
+            format!("{}: {title}", self.name)
+        } else {
+            title.to_string()
+        }
+    }
+
+    fn tab(&self, title: &str, several: bool, entries: Arc<parser::Entries>) -> TabContent {
+        let mut tab = TabContent::new(self.tab_title(title, several), entries);
+        tab.bundle = Some(self.hash);
+        tab
+    }
+
+    // None when the services are not part of this bundle
+    fn source_tab(&self, title: &str, several: bool, source: TabSource) -> Option<TabContent> {
+        let logbook = &self.logs.logbook;
+        let mut tab = match &source {
+            TabSource::Service(service) => self.tab(title, several, logbook.get(service)?.clone()),
+            TabSource::Merged(services) => {
+                if !services.iter().all(|service| logbook.contains_key(service)) {
+                    return None;
+                }
+                let entries = parser::merge_services(logbook, services);
+                self.tab(title, several, Arc::new(entries))
+            }
+            TabSource::AllServices => {
+                let entries = parser::merge_services(logbook, logbook.keys());
+                let mut tab = self.tab(title, several, Arc::new(entries));
+                tab.is_search = false;
+                tab
+            }
+            TabSource::ErrorSummary { include_warnings } => {
+                let mut tab = error_summary(logbook, *include_warnings);
+                tab.title = self.tab_title(title, several);
+                tab.bundle = Some(self.hash);
+                tab
+            }
+            TabSource::Dashboard => {
+                let mut tab = self.tab(title, several, Default::default());
+                tab.dashboard = Some(Dashboard::new(&self.logs));
+                tab
+            }
+        };
+        tab.source = Some(source);
+        Some(tab)
+    }
+
+    // Lists the services matching the search, the ones without entries in the date range grayed
+    // out, returns the tabs to open
+    fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        several: bool,
+        search: &str,
+        (first_date, second_date): (DateTime<Utc>, DateTime<Utc>),
+        palette: &Palette,
+    ) -> Vec<TabContent> {
+        let mut opened = vec![];
+        if ui.button("All services").clicked() {
+            opened.extend(self.source_tab("All services", several, TabSource::AllServices));
+        }
+        if ui
+            .add_enabled(
+                self.selected_services.len() > 1,
+                egui::Button::new("Merge selected"),
+            )
+            .clicked()
+        {
+            let services: Vec<String> = self.selected_services.iter().cloned().collect();
+            let title = services.join(" + ");
+            opened.extend(self.source_tab(&title, several, TabSource::Merged(services)));
+        }
+        ui.separator();
+
+        let skipped_services = &self.logs.skipped_services;
+        if !skipped_services.is_empty() {
+            ui.label(
+                RichText::new(format!("{} services not loaded", skipped_services.len()))
+                    .color(palette.levels.get(&LogLevel::Warn)),
+            )
+            .on_hover_text(
+                skipped_services
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            );
+            ui.separator();
+        }
+
+        let empty_services = &self.logs.empty_services;
+        if !empty_services.is_empty() {
+            ui.label(RichText::new(format!("{} empty services", empty_services.len())).weak())
+                .on_hover_text(format!(
+                    "Their files had no log entries:\n{}",
+                    empty_services
+                        .iter()
+                        .cloned()
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                ));
+            ui.separator();
+        }
+
+        let unparsed_lines: usize = self.logs.stats.values().map(|stats| stats.unparsed).sum();
+        if unparsed_lines > 0 {
+            let text = if self.kept_unparsed {
+                format!("{unparsed_lines} unparsed lines kept as UNKNOWN")
+            } else {
+                format!("{unparsed_lines} unparsed lines dropped")
+            };
+            ui.label(RichText::new(text).color(palette.levels.get(&LogLevel::Warn)))
+                .on_hover_text(
+                    "Lines before the first entry of their file that no format matched, \
+                     see View > Keep unparsed lines and View > Parsing profiles",
+                );
+            ui.separator();
+        }
+
+        if let Some(follow) = &mut self.follow {
+            ui.checkbox(&mut follow.enabled, "Follow").on_hover_text(
+                "Read the lines appended to the file every second and scroll to them",
+            );
+            ui.separator();
+        }
+
+        let mut disconnected = false;
+        if let Some(live) = &self.live {
+            let state = live.state();
+            let color = match state {
+                LiveState::Connected => Color32::GREEN,
+                LiveState::Connecting => ui.visuals().text_color(),
+                LiveState::Reconnecting(_) => palette.levels.get(&LogLevel::Warn),
+            };
+            ui.label(RichText::new(state.to_string()).color(color))
+                .on_hover_text(&live.url);
+            let dropped = live.dropped();
+            if dropped > 0 {
+                ui.label(RichText::new(format!("{dropped} entries dropped")).weak())
+                    .on_hover_text("Entries that came faster than they could be shown");
+            }
+            disconnected = ui.button("Disconnect").clicked();
+            ui.separator();
+        }
+        if disconnected {
+            if let Some(live) = self.live.take() {
+                live.disconnect();
+            }
+        }
+
+        for (service, entries) in fuzzy::filter(search, &self.logs.logbook, |(service, _)| service)
+        {
+            let selected_services = &mut self.selected_services;
+            let clicked = ui
+                .horizontal(|ui| {
+                    let mut selected = selected_services.contains(service);
+                    if ui.checkbox(&mut selected, "").changed() {
+                        if selected {
+                            selected_services.insert(service.clone());
+                        } else {
+                            selected_services.remove(service);
+                        }
+                    }
+                    // Entries are sorted, the first one after the start tells if any is in range
+                    let first = entries.partition_point(|entry| entry.timestamp < first_date);
+                    let active = entries.get(first).is_some_and(|entry| {
+                        in_date_range(entry.timestamp, first_date, second_date)
+                    });
+                    let text = if active {
+                        RichText::new(service)
+                    } else {
+                        RichText::new(service).weak()
+                    };
+                    ui.button(text)
+                        .on_hover_ui(|ui| {
+                            if !active {
+                                ui.label("No entries in the date range");
+                            }
+                            line_stats_ui(ui, self.logs.stats.get(service), entries, palette)
+                        })
+                        .clicked()
+                })
+                .inner;
+            if clicked {
+                let source = TabSource::Service(service.clone());
+                opened.extend(self.source_tab(service, several, source));
+            }
+        }
+        opened
+    }
+}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+enum TabSource {
+    Service(String),
+    Merged(Vec<String>),
+    AllServices,
+    ErrorSummary { include_warnings: bool },
+    Dashboard,
+}
+
+// What is kept of a tab across sessions, its entries are taken again from the bundle
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct TabLayout {
+    title: String,
+    source: TabSource,
+}
+
+#[derive(Default)]
+struct CommandPalette {
+    query: String,
+    selected: usize,
+}
+
+#[derive(Clone)]
+enum Command {
+    // Service of the bundle at this index
+    OpenService(usize, String),
+    OpenBundle,
+    ExportAll,
+    ErrorSummary,
+    ToggleDarkMode,
+    CloseAllTabs,
+    Shortcuts,
+}
+
+struct Compare {
+    left: String,
+    right: String,
+    // Entries further apart than this are never paired
+    window_seconds: u64,
+    align_starts: bool,
+}
+
+fn change_color(change: Change, palette: &Palette) -> Color32 {
+    match change {
+        Change::Same => Color32::GRAY,
+        Change::Changed => palette.levels.get(&LogLevel::Warn),
+        Change::Removed => palette.levels.get(&LogLevel::Error),
+        Change::Added => palette.added,
+    }
+}
+
+struct DiffView {
+    title: String,
+    rows: Vec<diff::DiffRow>,
+    hide_same: bool,
+}
+
+impl DiffView {
+    fn new(compare: &Compare, left: &[LogEntry], right: &[LogEntry]) -> Self {
+        let window = chrono::TimeDelta::seconds(compare.window_seconds as i64);
+        Self {
+            title: format!("{} ↔ {}", compare.left, compare.right),
+            rows: diff::diff(left, right, window, compare.align_starts),
+            hide_same: true,
+        }
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, palette: &Palette) {
+        use egui_extras::{Column, TableBuilder};
+
+        ui.horizontal(|ui| {
+            for change in [Change::Removed, Change::Added, Change::Changed] {
+                let count = self.rows.iter().filter(|row| row.change == change).count();
+                ui.label(
+                    RichText::new(format!("{count} {change:?}"))
+                        .color(change_color(change, palette)),
+                );
+            }
+            ui.separator();
+            ui.checkbox(&mut self.hide_same, "Hide unchanged");
+        });
+        ui.separator();
+
+        let rows: Vec<&diff::DiffRow> = self
+            .rows
+            .iter()
+            .filter(|row| !self.hide_same || row.change != Change::Same)
+            .collect();
+        let text_height = egui::TextStyle::Body
+            .resolve(ui.style())
+            .size
+            .max(ui.spacing().interact_size.y);
+        let message = |ui: &mut egui::Ui, entry: &Option<LogEntry>, change: Change| {
+            if let Some(entry) = entry {
+                let text = RichText::new(&entry.message).color(change_color(change, palette));
+                ui.add(egui::Label::new(text).truncate(true))
+                    .on_hover_text(entry.timestamp.to_string());
+            }
+        };
+        TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .column(Column::auto())
+            .columns(Column::remainder().clip(true), 2)
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("Offset");
+                });
+                header.col(|ui| {
+                    ui.strong("Left");
+                });
+                header.col(|ui| {
+                    ui.strong("Right");
+                });
+            })
+            .body(|body| {
+                body.rows(text_height, rows.len(), |mut row| {
+                    let diff_row = rows[row.index()];
+                    row.col(|ui| {
+                        ui.label(format_delta(diff_row.offset));
+                    });
+                    row.col(|ui| message(ui, &diff_row.left, diff_row.change));
+                    row.col(|ui| message(ui, &diff_row.right, diff_row.change));
+                });
+            });
+    }
+}
+
+const DASHBOARD_TOP_MESSAGES: usize = 10;
+const DASHBOARD_BUSIEST_MINUTES: usize = 5;
+
+struct ServiceSummary {
+    service: String,
+    entries: usize,
+    errors: usize,
+    warnings: usize,
+}
+
+// Health overview of a bundle, shown as its first tab
+struct Dashboard {
+    first: DateTime<Utc>,
+    last: DateTime<Utc>,
+    size: usize,
+    processing: chrono::TimeDelta,
+    services: Vec<ServiceSummary>,
+    // Minutes with the most entries, across services
+    busiest: Vec<(DateTime<Utc>, usize)>,
+    // Most repeated messages with their service
+    top_messages: Vec<(usize, String, String)>,
+}
+
+impl Dashboard {
+    fn new(logs: &parser::Processed) -> Self {
+        let entries = || logs.logbook.values().flat_map(|entries| entries.iter());
+        let services = logs
+            .logbook
+            .iter()
+            .map(|(service, entries)| {
+                let count = |level| entries.iter().filter(|entry| entry.level == level).count();
+                ServiceSummary {
+                    service: service.clone(),
+                    entries: entries.len(),
+                    errors: count(LogLevel::Error),
+                    warnings: count(LogLevel::Warn),
+                }
+            })
+            .collect();
+
+        let mut minutes: HashMap<i64, usize> = HashMap::new();
+        for entry in entries() {
+            *minutes.entry(entry.timestamp.timestamp() / 60).or_default() += 1;
+        }
+        let mut busiest: Vec<(DateTime<Utc>, usize)> = minutes
+            .into_iter()
+            .filter_map(|(minute, count)| Some((DateTime::from_timestamp(minute * 60, 0)?, count)))
+            .collect();
+        busiest.sort_by_key(|(minute, count)| (Reverse(*count), *minute));
+        busiest.truncate(DASHBOARD_BUSIEST_MINUTES);
+
+        let mut messages: HashMap<(&str, &str), usize> = HashMap::new();
+        for (service, entries) in &logs.logbook {
+            for entry in entries.iter() {
+                let message = entry.message.lines().next().unwrap_or_default();
+                *messages.entry((service, message)).or_default() += 1;
+            }
+        }
+        let mut top_messages: Vec<(usize, String, String)> = messages
+            .into_iter()
+            .map(|((service, message), count)| (count, service.into(), message.into()))
+            .collect();
+        top_messages.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.cmp(b)));
+        top_messages.truncate(DASHBOARD_TOP_MESSAGES);
+
+        Self {
+            first: entries()
+                .map(|entry| entry.timestamp)
+                .min()
+                .unwrap_or_default(),
+            last: entries()
+                .map(|entry| entry.timestamp)
+                .max()
+                .unwrap_or_default(),
+            size: logs.size,
+            processing: logs.duration,
+            services,
+            busiest,
+            top_messages,
+        }
+    }
+
+    fn ui(&self, ui: &mut egui::Ui, timestamps: &TimestampFormat, palette: &Palette) {
+        egui::ScrollArea::vertical()
+            .auto_shrink(false)
+            .show(ui, |ui| {
+                ui.heading("Overview");
+                egui::Grid::new("dashboard_overview").show(ui, |ui| {
+                    ui.label("Logs from");
+                    ui.label(format!(
+                        "{} to {}",
+                        timestamps.format(self.first, self.first),
+                        timestamps.format(self.last, self.first)
+                    ));
+                    ui.end_row();
+                    ui.label("Covering");
+                    let span = (self.last - self.first).to_std().unwrap_or_default();
+                    ui.label(
+                        humantime::format_duration(std::time::Duration::from_secs(span.as_secs()))
+                            .to_string(),
+                    );
+                    ui.end_row();
+                    ui.label("Size");
+                    ui.label(bytesize::ByteSize(self.size as u64).to_string());
+                    ui.end_row();
+                    ui.label("Processed in");
+                    ui.label(
+                        humantime::format_duration(self.processing.to_std().unwrap_or_default())
+                            .to_string(),
+                    );
+                    ui.end_row();
+                });
+
+                ui.separator();
+                ui.heading("Services");
+                egui::Grid::new("dashboard_services")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Service");
+                        ui.strong("Entries");
+                        ui.strong(
+                            RichText::new("Errors").color(palette.levels.get(&LogLevel::Error)),
+                        );
+                        ui.strong(
+                            RichText::new("Warnings").color(palette.levels.get(&LogLevel::Warn)),
+                        );
+                        ui.end_row();
+                        for summary in &self.services {
+                            ui.label(&summary.service);
+                            ui.label(summary.entries.to_string());
+                            ui.label(summary.errors.to_string());
+                            ui.label(summary.warnings.to_string());
+                            ui.end_row();
+                        }
+                    });
+
+                ui.separator();
+                ui.heading("Busiest minutes");
+                egui::Grid::new("dashboard_busiest")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (minute, count) in &self.busiest {
+                            ui.label(timestamps.format(*minute, self.first));
+                            ui.label(format!("{count} entries"));
+                            ui.end_row();
+                        }
+                    });
+
+                ui.separator();
+                ui.heading("Most frequent messages");
+                egui::Grid::new("dashboard_messages")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (count, service, message) in &self.top_messages {
+                            ui.label(count.to_string());
+                            ui.label(service);
+                            ui.label(ansi::strip_ansi(message));
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
+
+// Tells at a glance whether the lines of a service were parsed as expected
+fn line_stats_ui(
+    ui: &mut egui::Ui,
+    stats: Option<&parser::LineStats>,
+    entries: &[LogEntry],
+    palette: &Palette,
+) {
+    egui::Grid::new("line_stats").show(ui, |ui| {
+        ui.label("Entries");
+        ui.label(entries.len().to_string());
+        ui.end_row();
+        // Services are sorted by timestamp once processed
+        if let (Some(first), Some(last)) = (entries.first(), entries.last()) {
+            ui.label("First entry");
+            ui.label(first.timestamp.to_string());
+            ui.end_row();
+            ui.label("Last entry");
+            ui.label(last.timestamp.to_string());
+            ui.end_row();
+        }
+        if let Some(stats) = stats {
+            for (name, count) in [
+                ("Lines read", stats.lines),
+                ("Entries parsed", stats.parsed),
+                ("Continuation lines", stats.continuations),
+                ("Unparsed lines", stats.unparsed),
+            ] {
+                ui.label(name);
+                ui.label(count.to_string());
+                ui.end_row();
+            }
+        }
+        for (level, count) in LogLevel::iter().zip(count_levels(entries)) {
+            ui.label(RichText::new(level.to_string()).color(palette.levels.get(&level)));
+            ui.label(count.to_string());
+            ui.end_row();
+        }
+    });
+}
+
+// A bundle picked from disk, kept to load it again unless it changed since
+#[derive(Clone)]
+struct BundleFile {
+    name: String,
+    source: parser::LogSource,
+    modified: Option<std::time::SystemTime>,
+}
+
+fn error_summary(logbook: &parser::LogBook, include_warnings: bool) -> TabContent {
+    let (entries, service_counts) = parser::summarize_errors(logbook, include_warnings);
+    let title = if include_warnings {
+        "Errors and warnings"
+    } else {
+        "Errors"
+    };
+    let mut tab = TabContent::new(title.into(), Arc::new(entries));
+    tab.show_service = true;
+    tab.service_counts = service_counts;
+    tab
+}
+
+// Terms of a field filter, see `TabContent::field_filter`
+fn field_terms(filter: &str) -> Vec<(&str, Option<&str>)> {
+    filter
+        .split_whitespace()
+        .map(|term| match term.split_once('=') {
+            Some((key, value)) => (key, Some(value)),
+            None => (term, None),
+        })
+        .collect()
+}
+
+fn fields_match(terms: &[(&str, Option<&str>)], message: &str) -> bool {
+    if terms.is_empty() {
+        return true;
+    }
+    let fields = parser::message_fields(message);
+    terms.iter().all(|(key, value)| {
+        fields
+            .iter()
+            .any(|(field, other)| field == key && value.map_or(true, |value| value == *other))
+    })
+}
+
+// Matches with the entries around them, and whether each entry is only there as context.
+// Both lists are in the order of the entries and the matches are among the shown ones.
+fn with_context(shown: &[usize], matches: &[usize], lines: usize) -> (Vec<usize>, Vec<bool>) {
+    // 0 for entries left out, 1 for context, 2 for matches
+    let mut kept = vec![0u8; shown.len()];
+    for index in matches {
+        let Ok(position) = shown.binary_search(index) else {
+            continue;
+        };
+        let end = (position + lines + 1).min(shown.len());
+        for kept in &mut kept[position.saturating_sub(lines)..end] {
+            *kept = (*kept).max(1);
+        }
+        kept[position] = 2;
+    }
+    shown
+        .iter()
+        .zip(kept)
+        .filter(|(_, kept)| *kept > 0)
+        .map(|(&index, kept)| (index, kept == 1))
+        .unzip()
+}
+
+// Filter and notes saved for the tab title
+fn restore_tab(
+    tab_filters: &BTreeMap<String, TabFilter>,
+    saved_notes: &SavedNotes,
+    mut tab: TabContent,
+) -> TabContent {
+    if let Some(tab_filter) = tab_filters.get(&tab.title) {
+        tab_filter.apply(&mut tab);
+    }
+    if let Some(notes) = tab
+        .bundle
+        .and_then(|bundle| saved_notes.get(&bundle))
+        .and_then(|tabs| tabs.get(&tab.title))
+    {
+        tab.notes = notes.iter().cloned().collect();
+    }
+    tab
+}
+
+fn find_tab(
+    tree: &DockState<TabContent>,
+    title: &str,
+) -> Option<(SurfaceIndex, NodeIndex, egui_dock::TabIndex)> {
+    tree.iter_surfaces()
+        .enumerate()
+        .find_map(|(surface_index, surface)| {
+            surface
+                .iter_nodes()
+                .enumerate()
+                .find_map(|(node_index, node)| match node {
+                    egui_dock::Node::Leaf { tabs, .. } => {
+                        let tab_index = tabs.iter().position(|tab| tab.title == title)?;
+                        Some((
+                            SurfaceIndex(surface_index),
+                            NodeIndex(node_index),
+                            egui_dock::TabIndex(tab_index),
+                        ))
+                    }
+                    _ => None,
+                })
+        })
+}
+
+// Adds entries to a service of a bundle and to the tabs showing it
+fn append_entries(
+    tree: &mut DockState<TabContent>,
+    bundle: &mut Bundle,
+    service: &str,
+    mut appended: Vec<LogEntry>,
+) {
+    if appended.is_empty() {
+        return;
+    }
+    let Some(entries) = bundle.logs.logbook.get_mut(service) else {
+        return;
+    };
+    let shared = Arc::make_mut(entries);
+    shared.append(&mut appended);
+    shared.sort_by_key(|entry| entry.timestamp);
+    for (_, tab) in tree.iter_all_tabs_mut() {
+        let shows_service = matches!(
+            &tab.source,
+            Some(TabSource::Service(other)) if other == service
+        );
+        if tab.bundle == Some(bundle.hash) && shows_service {
+            tab.set_entries(entries.clone());
+        }
+    }
+}
+
+// Tabs already open are shown instead of opened again
+fn open_tab(
+    tree: &mut DockState<TabContent>,
+    tab_filters: &BTreeMap<String, TabFilter>,
+    saved_notes: &SavedNotes,
+    tab: TabContent,
+    in_window: bool,
+) {
+    let tab = restore_tab(tab_filters, saved_notes, tab);
+    if let Some((surface, node, index)) = find_tab(tree, &tab.title) {
+        // Unless it shows a bundle loaded before
+        if let egui_dock::Node::Leaf { tabs, .. } = &mut tree[surface][node] {
+            if tabs[index.0].bundle != tab.bundle {
+                tabs[index.0] = tab;
+            }
+        }
+        tree.set_active_tab((surface, node, index));
+        tree.set_focused_node_and_surface((surface, node));
+        return;
+    }
+
+    if in_window && tree.main_surface().num_tabs() > 0 {
+        tree.add_window(vec![tab]);
+    } else {
+        tree.main_surface_mut().push_to_focused_leaf(tab);
+    }
+}
+
+const TOP_PATTERNS: usize = 20;
+
+// Entries shown only as the context of a match are grayed out
+const CONTEXT_OPACITY: f32 = 0.45;
+
+fn frequent_patterns(entries: &[LogEntry]) -> Vec<(usize, String)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for entry in entries {
+        *counts
+            .entry(parser::message_pattern(&entry.message))
+            .or_default() += 1;
+    }
+    let mut patterns: Vec<(usize, String)> = counts
+        .into_iter()
+        .map(|(pattern, count)| (count, pattern))
+        .collect();
+    patterns.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    patterns.truncate(TOP_PATTERNS);
+    patterns
+}
+
+// Matches the first line of the messages a pattern came from
+fn pattern_regex(pattern: &str) -> String {
+    let parts: Vec<String> = pattern
+        .split(parser::PATTERN_PLACEHOLDER)
+        .map(regex::escape)
+        .collect();
+    format!("^{}(?m:$)", parts.join(r"\S+?"))
+}
+
+// Entry and line indices of every line of the entries as they were in the file
+fn raw_lines(entries: FilteredEntries<'_>) -> Vec<(usize, usize)> {
+    entries
+        .iter()
+        .enumerate()
+        .flat_map(|(index, entry)| {
+            (0..entry.raw.lines().count().max(1)).map(move |line| (index, line))
+        })
+        .collect()
+}
+
+fn raw_ui(
+    ui: &mut egui::Ui,
+    entries: FilteredEntries<'_>,
+    raw_lines: &[(usize, usize)],
+    rx: &regex::Regex,
+    highlight: Color32,
+    font_id: &egui::FontId,
+    scroll_to_entry: Option<usize>,
+) {
+    let text_height = font_id.size.max(ui.spacing().interact_size.y);
+    let mut scroll_area = egui::ScrollArea::both().id_source("raw").auto_shrink(false);
+    if let Some(entry) = scroll_to_entry {
+        let line = raw_lines.partition_point(|(index, _)| *index < entry);
+        scroll_area = scroll_area
+            .vertical_scroll_offset(line as f32 * (text_height + ui.spacing().item_spacing.y));
+    }
+    let format = TextFormat {
+        font_id: font_id.clone(),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+    scroll_area.show_rows(ui, text_height, raw_lines.len(), |ui, range| {
+        for &(index, line) in &raw_lines[range] {
+            let text = entries[index].raw.lines().nth(line).unwrap_or_default();
+            let mut job = LayoutJob::default();
+            highlight_text_in_ui(text, rx, format.clone(), highlight, &mut job);
+            ui.add(egui::Label::new(job).wrap(false));
+        }
+    });
+}
+
+fn highlight_text_in_ui(
+    message: &str,
+    rx: &regex::Regex,
+    format: TextFormat,
+    highlight: Color32,
+    job: &mut LayoutJob,
+) {
+    let mut last_end = 0;
+
+    // Iterate over all matches in the message
+    for mat in rx.find_iter(message).filter(|mat| !mat.is_empty()) {
+        if last_end != mat.start() {
+            // Add non-matching text with the base formatting
+            job.append(&message[last_end..mat.start()], 0.0, format.clone());
+        }
+        // Add matching text with highlighted formatting
+        job.append(
+            &message[mat.start()..mat.end()],
+            0.0,
+            TextFormat {
+                color: Color32::BLACK,
+                background: highlight,
+                ..format.clone()
+            },
+        );
+        last_end = mat.end();
+    }
+
+    if last_end < message.len() {
+        // Add remaining non-matching text
+        job.append(&message[last_end..], 0.0, format);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn request_notifications() {}
+
+// Browsers only show notifications once allowed
+#[cfg(target_arch = "wasm32")]
+fn request_notifications() {
+    if let Err(e) = web_sys::Notification::request_permission() {
+        log::warn!("Failed to request notifications: {:#?}", e);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn notify_loaded(ctx: &egui::Context, _name: String) {
+    ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(
+        egui::UserAttentionType::Informational,
+    ));
+}
+
+#[cfg(target_arch = "wasm32")]
+fn notify_loaded(_ctx: &egui::Context, name: String) {
+    use web_sys::{Notification, NotificationOptions, NotificationPermission};
+    if Notification::permission() != NotificationPermission::Granted {
+        return;
+    }
+    let options = NotificationOptions::new();
+    options.set_body(&format!("{name} is loaded"));
+    if let Err(e) = Notification::new_with_options("BlueOS Log Viewer", &options) {
+        log::warn!("Failed to notify: {:#?}", e);
+    }
+}
+
+fn default_service_enabled(name: &str) -> bool {
+    !name.contains("camera")
+}
+
+impl TemplateApp {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        // Load previous app state (if any).
+        #[allow(unused_mut)]
+        let mut app: Self = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+        #[cfg(target_arch = "wasm32")]
+        {
+            app.shared_view = share::read();
+        }
+        app
+    }
+
+    fn allowed_services(&self) -> Vec<String> {
+        self.service_names
+            .iter()
+            .filter(|(_, &value)| value)
+            .map(|(name, _)| name)
+            .cloned()
+            .collect()
+    }
+
+    fn parse_options(&self) -> parser::ParseOptions {
+        parser::ParseOptions {
+            // Invalid profiles are left out, their error is shown where they are edited
+            profiles: self
+                .profiles
+                .iter()
+                .filter(|profile| profile.enabled)
+                .filter_map(|profile| profile.regex().ok())
+                .collect(),
+            keep_unparsed: self.keep_unparsed,
+        }
+    }
+
+    // Services of every bundle, those of different bundles are told apart by the bundle name
+    fn logbook(&self) -> Cow<'_, parser::LogBook> {
+        match self.bundles.as_slice() {
+            [bundle] => Cow::Borrowed(&bundle.logs.logbook),
+            bundles => Cow::Owned(
+                bundles
+                    .iter()
+                    .flat_map(|bundle| {
+                        bundle.logs.logbook.iter().map(|(service, entries)| {
+                            (bundle.tab_title(service, true), entries.clone())
+                        })
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    fn open_error_summary(&mut self, include_warnings: bool) {
+        let mut tab = error_summary(&self.logbook(), include_warnings);
+        if let [bundle] = self.bundles.as_slice() {
+            tab.bundle = Some(bundle.hash);
+            tab.source = Some(TabSource::ErrorSummary { include_warnings });
+        }
+        open_tab(
+            &mut self.tree,
+            &self.tab_filters,
+            &self.saved_notes,
+            tab,
+            false,
+        );
+    }
+
+    // Every service merged in time order, as a single system log
+    fn export_all(&self) {
+        let logbook = self.logbook();
+        let first_date = self.tab_viewer.first_date;
+        let second_date = self.tab_viewer.second_date;
+        let entries: Vec<LogEntry> = parser::merge_services(&logbook, logbook.keys())
+            .into_iter()
+            .filter(|entry| {
+                self.export_level
+                    .as_ref()
+                    .map_or(true, |level| entry.level >= *level)
+            })
+            .filter(|entry| {
+                !self.export_date_range || in_date_range(entry.timestamp, first_date, second_date)
+            })
+            .collect();
+        let start = entries
+            .first()
+            .map(|entry| entry.timestamp)
+            .unwrap_or_default();
+        let name = match self.bundles.as_slice() {
+            [bundle] => bundle.name.trim_end_matches(".zip"),
+            _ => "logs",
+        };
+        export::download_file(
+            &format!("{name}.txt"),
+            ExportFormat::Txt
+                .export(&entries, true, &self.tab_viewer.timestamp_format, start)
+                .into_bytes(),
+        );
+    }
+
+    fn close_tabs(&mut self, close: CloseTabs) {
+        // Closing the last tab of the main surface through the dock breaks it, so start anew
+        let kept = match close {
+            CloseTabs::All => None,
+            CloseTabs::Others(title) => self
+                .tree
+                .iter_all_tabs_mut()
+                .find(|(_, tab)| tab.title == title)
+                .map(|(_, tab)| std::mem::replace(tab, TabContent::new(title, Default::default()))),
+        };
+        self.tree = DockState::new(kept.into_iter().collect());
+    }
+
+    fn start_loading(&mut self, name: String, worker: parser::Worker) {
+        *self.worker.lock().unwrap() = worker;
+        if !std::mem::take(&mut self.keep_bundles) {
+            self.bundles.clear();
+        }
+        self.loading_name = name;
+        self.loading_path = None;
+        self.is_processing = true;
+    }
+
+    // Reads what was appended to the followed files and what the vehicles streamed, once a
+    // second since every read copies the entries still shown by the tabs
+    fn read_growing_bundles(&mut self, ctx: &egui::Context) {
+        let now = ctx.input(|i| i.time);
+        #[cfg(not(target_arch = "wasm32"))]
+        let options = self.parse_options();
+        for bundle in &mut self.bundles {
+            let following = bundle.follow.as_ref().is_some_and(|follow| follow.enabled);
+            if !following && bundle.live.is_none() {
+                continue;
+            }
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+            if now - bundle.read_at < 1.0 {
+                continue;
+            }
+            bundle.read_at = now;
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(follow) = bundle.follow.as_mut().filter(|follow| follow.enabled) {
+                match parser::read_appended(&follow.path, follow.offset, &options) {
+                    Ok((appended, offset)) => {
+                        follow.offset = offset;
+                        let service = follow.service.clone();
+                        append_entries(&mut self.tree, bundle, &service, appended);
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to follow {}: {e}", follow.path.display());
+                        follow.enabled = false;
+                    }
+                }
+            }
+            if let Some(live) = &bundle.live {
+                let received = live.take_entries();
+                append_entries(&mut self.tree, bundle, live::SERVICE, received);
+            }
+        }
+    }
+
+    // Everything about the selected entry, below the tabs
+    fn inspector_ui(&mut self, ctx: &egui::Context) {
+        let Some(selected) = &self.tab_viewer.selected_entry else {
+            return;
+        };
+        let entry = &selected.entry;
+        let mut close = false;
+        let mut picked_field = None;
+        egui::TopBottomPanel::bottom("inspector")
+            .resizable(true)
+            .default_height(180.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.strong(format!("Entry of {}", selected.tab));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        close = ui.small_button("✖").on_hover_text("Close").clicked();
+                    });
+                });
+                egui::ScrollArea::vertical()
+                    .auto_shrink(false)
+                    .show(ui, |ui| {
+                        egui::Grid::new("inspector").num_columns(2).show(ui, |ui| {
+                            ui.label("Timestamp");
+                            ui.label(
+                                entry
+                                    .timestamp
+                                    .format("%Y-%m-%d %H:%M:%S%.9f UTC")
+                                    .to_string(),
+                            );
+                            ui.end_row();
+                            ui.label("Level");
+                            ui.label(
+                                RichText::new(entry.level.to_string())
+                                    .color(self.tab_viewer.palette.levels.get(&entry.level)),
+                            );
+                            ui.end_row();
+                            let details = [
+                                ("Component", &entry.component),
+                                ("Service", &entry.service),
+                                ("Source file", &entry.source_file),
+                            ];
+                            for (name, value) in details {
+                                if let Some(value) = value {
+                                    ui.label(name);
+                                    ui.label(value);
+                                    ui.end_row();
+                                }
+                            }
+                        });
+
+                        ui.separator();
+                        let font_id = egui::FontId::monospace(self.tab_viewer.font_size);
+                        let mut job = LayoutJob::default();
+                        create_layout_from_terminal_escape_sequence(
+                            &entry.message,
+                            &TextFormat {
+                                font_id: font_id.clone(),
+                                ..Default::default()
+                            },
+                            ui.visuals().strong_text_color(),
+                            &mut job,
+                        );
+                        ui.add(egui::Label::new(job).wrap(false));
+                        if let Some(traceback) = &entry.traceback {
+                            ui.add(
+                                egui::Label::new(RichText::new(traceback).font(font_id).weak())
+                                    .wrap(false),
+                            );
+                        }
+
+                        let fields = parser::message_fields(&entry.message);
+                        if !fields.is_empty() {
+                            ui.separator();
+                            egui::Grid::new("inspector fields")
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for (key, value) in fields {
+                                        if ui
+                                            .link(key)
+                                            .on_hover_text(
+                                                "Filter the tab to entries with this value",
+                                            )
+                                            .clicked()
+                                        {
+                                            picked_field = Some(format!("{key}={value}"));
+                                        }
+                                        ui.label(value);
+                                        ui.end_row();
+                                    }
+                                });
+                        }
+                    });
+            });
+
+        if let Some(field) = picked_field {
+            let title = &selected.tab;
+            if let Some((_, tab)) = self
+                .tree
+                .iter_all_tabs_mut()
+                .find(|(_, tab)| tab.title == *title)
+            {
+                tab.add_field_filter(field);
+            }
+        }
+        if close {
+            self.tab_viewer.selected_entry = None;
+        }
+    }
+
+    fn connect_live(&mut self) {
+        let url = self.live_url.trim().to_string();
+        let live = if self.live_polling {
+            let interval = std::time::Duration::from_secs_f32(self.poll_interval);
+            live::Live::poll(url, interval, self.parse_options())
+        } else {
+            live::Live::connect(url, self.parse_options())
+        };
+        self.loading_name = "Live".into();
+        self.add_bundle(parser::Processed {
+            logbook: BTreeMap::from([(live::SERVICE.to_string(), Default::default())]),
+            ..Default::default()
+        });
+        let several = self.bundles.len() > 1;
+        let Some(bundle) = self.bundles.last_mut() else {
+            return;
+        };
+        bundle.live = Some(live);
+        let source = TabSource::Service(live::SERVICE.into());
+        if let Some(tab) = bundle.source_tab(live::SERVICE, several, source) {
+            open_tab(
+                &mut self.tree,
+                &self.tab_filters,
+                &self.saved_notes,
+                tab,
+                false,
+            );
+        }
+    }
+
+    fn add_bundle(&mut self, mut logs: parser::Processed) {
+        let hash = bundle_hash(&logs.logbook);
+        if self.dedup_entries {
+            for entries in logs.logbook.values_mut() {
+                parser::dedup_entries(Arc::make_mut(entries));
+            }
+        }
+        if self.guess_unknown_levels {
+            for entries in logs.logbook.values_mut() {
+                parser::guess_unknown_levels(Arc::make_mut(entries).as_mut_slice());
+            }
+        }
+        // Continues after the last byte parsed, lines appended while processing included
+        let follow = self.loading_path.take().and_then(|path| {
+            let service = logs.logbook.keys().next()?.clone();
+            Some(Follow {
+                path,
+                service,
+                offset: logs.consumed,
+                enabled: false,
+            })
+        });
+        let mut name = std::mem::take(&mut self.loading_name);
+        while self.bundles.iter().any(|bundle| bundle.name == name) {
+            name += "+";
+        }
+        let bundle = Bundle {
+            name,
+            logs,
+            hash,
+            kept_unparsed: self.keep_unparsed,
+            selected_services: Default::default(),
+            follow,
+            live: None,
+            read_at: 0.0,
+        };
+        let several = !self.bundles.is_empty();
+        if !several && self.tree.iter_all_tabs().next().is_none() {
+            if let Some(layout) = &self.layout {
+                self.tree = layout.filter_map_tabs(|layout: &TabLayout| {
+                    let tab = bundle.source_tab(&layout.title, false, layout.source.clone())?;
+                    Some(restore_tab(&self.tab_filters, &self.saved_notes, tab))
+                });
+                // Windows whose tabs are gone are dropped, the focus may point to one of them
+                self.tree
+                    .set_focused_node_and_surface((SurfaceIndex::main(), NodeIndex::root()));
+            }
+        }
+        if self.tree.main_surface().num_tabs() == 0 {
+            self.tree = DockState::new(vec![]);
+            if let Some(tab) = bundle.source_tab("Dashboard", several, TabSource::Dashboard) {
+                open_tab(
+                    &mut self.tree,
+                    &self.tab_filters,
+                    &self.saved_notes,
+                    tab,
+                    false,
+                );
+            }
+        }
+        self.bundles.push(bundle);
+        self.open_shared_view();
+    }
+
+    // Opens the tab of a shared link once a bundle with its service is there
+    fn open_shared_view(&mut self) {
+        let several = self.bundles.len() > 1;
+        let Some(view) = self.shared_view.take() else {
+            return;
+        };
+        let Some(bundle) = self
+            .bundles
+            .last()
+            .filter(|bundle| bundle.logs.logbook.contains_key(&view.service))
+        else {
+            self.shared_view = Some(view);
+            return;
+        };
+        let source = TabSource::Service(view.service.clone());
+        let Some(tab) = bundle.source_tab(&view.service, several, source) else {
+            return;
+        };
+        let title = tab.title.clone();
+        open_tab(
+            &mut self.tree,
+            &self.tab_filters,
+            &self.saved_notes,
+            tab,
+            false,
+        );
+        // The filters of the link win over the ones saved for the tab
+        if let Some((_, tab)) = self
+            .tree
+            .iter_all_tabs_mut()
+            .find(|(_, tab)| tab.title == title)
+        {
+            tab.filter = view.filter;
+            tab.use_regex = view.use_regex;
+            tab.is_search = view.is_search;
+            tab.enabled_levels = view.levels;
+            tab.dirty = true;
+        }
+        if let Some((first, second)) = view.range {
+            self.tab_viewer.first_date = first;
+            self.tab_viewer.second_date = second;
+        }
+    }
+
+    // Keeps the view of the focused service tab in the URL, to be shared
+    #[cfg(target_arch = "wasm32")]
+    fn share_view(&mut self) {
+        if self.shared_view.is_some() {
+            return;
+        }
+        let Some((_, tab)) = self.tree.find_active_focused() else {
+            return;
+        };
+        let Some(TabSource::Service(service)) = &tab.source else {
+            return;
+        };
+        let hash = share::View {
+            service: service.clone(),
+            filter: tab.filter.clone(),
+            use_regex: tab.use_regex,
+            is_search: tab.is_search,
+            levels: tab.enabled_levels.clone(),
+            range: Some((self.tab_viewer.first_date, self.tab_viewer.second_date)),
+        }
+        .to_hash();
+        if hash != self.shared_hash {
+            share::write(&hash);
+            self.shared_hash = hash;
+        }
+    }
+
+    // Keeping the open bundles loads the new one next to them
+    fn select_bundle(&mut self, keep_bundles: bool) {
+        #[cfg(target_arch = "wasm32")]
+        let picked = async_std::task::block_on(async move {
+            let file = rfd::AsyncFileDialog::new()
+                .add_filter("Log bundle", &["zip"])
+                .pick_file()
+                .await?;
+            Some((
+                file.file_name(),
+                parser::LogSource::Data(file.read().await.into()),
+            ))
+        });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let picked = rfd::FileDialog::new()
+            .add_filter("Log bundle", &["zip"])
+            .pick_file()
+            .map(|path| {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                (name, parser::LogSource::Path(path))
+            });
+
+        let Some((name, source)) = picked else {
+            return;
+        };
+        let source = BundleFile {
+            name,
+            modified: source.modified(),
+            source,
+        };
+        self.discover_services(source, keep_bundles);
+    }
+
+    // Asks for the services of the last bundle again, unless it changed on disk since
+    fn reload_bundle(&mut self) {
+        let Some(source) = self.last_bundle.clone() else {
+            return;
+        };
+        if source.source.modified() != source.modified {
+            self.last_bundle = None;
+            *self.worker.lock().unwrap() = parser::Worker::failed(
+                "The bundle changed since it was loaded, select it again".into(),
+            );
+            return;
+        }
+        self.discover_services(source, false);
+    }
+
+    // The services modal opens once the worker lists them, see `show_discovered_services`
+    fn discover_services(&mut self, bundle: BundleFile, keep_bundles: bool) {
+        *self.worker.lock().unwrap() =
+            parser::discover_services(bundle.source.clone(), bundle.name.clone());
+        self.pending_bundle = Some(bundle);
+        self.keep_bundles = keep_bundles;
+        self.is_processing = true;
+    }
+
+    fn show_discovered_services(&mut self, services: BTreeSet<String>) {
+        for service in &services {
+            self.service_names
+                .entry(service.clone())
+                .or_insert_with(|| default_service_enabled(service));
+        }
+        self.discovered_services = services;
+        self.open_model = true;
+    }
+
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        use egui::{Key, Modifiers};
+
+        if ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND, Key::O)) {
+            self.select_bundle(false);
+        }
+        if ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND, Key::F)) {
+            if let Some((_, tab)) = self.tree.find_active_focused() {
+                tab.focus_search = true;
+            }
+        }
+        if ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND, Key::W)) {
+            if let Some((surface, node)) = self.tree.focused_leaf() {
+                if let egui_dock::Node::Leaf { active, .. } = self.tree[surface][node] {
+                    self.tree.remove_tab((surface, node, active));
+                }
+            }
+        }
+        if ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Escape)) {
+            if let Some((_, tab)) = self.tree.find_active_focused() {
+                tab.clear_filter = true;
+            }
+        }
+        if ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::F1)) {
+            self.show_shortcuts = !self.show_shortcuts;
+        }
+        if ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND, Key::P)) {
+            self.command_palette = match self.command_palette {
+                Some(_) => None,
+                None => Some(Default::default()),
+            };
+        }
+    }
+
+    fn commands(&self) -> Vec<(String, Command)> {
+        let several = self.bundles.len() > 1;
+        let mut commands: Vec<(String, Command)> = self
+            .bundles
+            .iter()
+            .enumerate()
+            .flat_map(|(index, bundle)| {
+                bundle.logs.logbook.keys().map(move |service| {
+                    let command = Command::OpenService(index, service.clone());
+                    (bundle.tab_title(service, several), command)
+                })
+            })
+            .collect();
+        commands.extend([
+            ("Open a log bundle".into(), Command::OpenBundle),
+            ("Export all services".into(), Command::ExportAll),
+            ("Error summary".into(), Command::ErrorSummary),
+            ("Toggle dark mode".into(), Command::ToggleDarkMode),
+            ("Close all tabs".into(), Command::CloseAllTabs),
+            ("Keyboard shortcuts".into(), Command::Shortcuts),
+        ]);
+        if self.bundles.is_empty() {
+            commands.retain(|(_, command)| {
+                !matches!(command, Command::ExportAll | Command::ErrorSummary)
+            });
+        }
+        commands
+    }
+
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::OpenService(index, service) => {
+                let several = self.bundles.len() > 1;
+                let source = TabSource::Service(service.clone());
+                if let Some(tab) = self
+                    .bundles
+                    .get(index)
+                    .and_then(|bundle| bundle.source_tab(&service, several, source))
+                {
+                    open_tab(
+                        &mut self.tree,
+                        &self.tab_filters,
+                        &self.saved_notes,
+                        tab,
+                        false,
+                    );
+                }
+            }
+            Command::OpenBundle => self.select_bundle(false),
+            Command::ExportAll => self.export_all(),
+            Command::ErrorSummary => self.open_error_summary(false),
+            Command::ToggleDarkMode => self.theme.dark_mode = !self.theme.dark_mode,
+            Command::CloseAllTabs => self.close_tabs(CloseTabs::All),
+            Command::Shortcuts => self.show_shortcuts = true,
+        }
+    }
+
+    // Services and actions picked by typing part of their name
+    fn command_palette_ui(&mut self, ctx: &egui::Context) {
+        use egui::{Key, Modifiers};
+
+        if self.command_palette.is_none() {
+            return;
+        }
+        let commands = self.commands();
+        let Some(palette) = &mut self.command_palette else {
+            return;
+        };
+        let matches = fuzzy::filter(&palette.query, commands, |(label, _)| label);
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::Enter),
+                i.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+        if up {
+            palette.selected = palette.selected.saturating_sub(1);
+        }
+        if down {
+            palette.selected += 1;
+        }
+        palette.selected = palette.selected.min(matches.len().saturating_sub(1));
 
-                        row.col(|ui| {
-                            let mut job = LayoutJob::default();
-                            if filter.is_empty() {
-                                create_layout_from_terminal_escape_sequence(
-                                    &entry.message,
-                                    &mut job,
-                                );
-                            } else {
-                                highlight_text_in_ui(entry.message.as_str(), rx, &mut job);
+        let mut picked = enter.then_some(palette.selected);
+        egui::Window::new("Command palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 40.0])
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut palette.query)
+                        .hint_text("Service or action")
+                        .desired_width(300.0),
+                );
+                response.request_focus();
+                if response.changed() {
+                    palette.selected = 0;
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
+                            for (index, (label, _)) in matches.iter().enumerate() {
+                                let response =
+                                    ui.selectable_label(index == palette.selected, label);
+                                if index == palette.selected && (up || down) {
+                                    response.scroll_to_me(None);
+                                }
+                                if response.clicked() {
+                                    picked = Some(index);
+                                }
                             }
-                            ui.label(job);
                         });
-
-                        if row.response().clicked() {
-                            dbg!(&entry.timestamp);
-                            self.selected_date = Some(entry.timestamp);
-                        }
                     });
-                });
-        });
-    }
-}
-
-fn highlight_text_in_ui(message: &str, rx: &regex::Regex, job: &mut LayoutJob) {
-    let mut last_end = 0;
+            });
 
-    // Iterate over all matches in the message
-    for mat in rx.find_iter(message) {
-        if last_end != mat.start() {
-            // Add non-matching text with default formatting
-            job.append(
-                &message[last_end..mat.start()],
-                0.0,
-                TextFormat {
-                    ..Default::default()
-                },
-            );
+        if escape {
+            self.command_palette = None;
+        } else if let Some((_, command)) = picked.and_then(|index| matches.into_iter().nth(index)) {
+            self.command_palette = None;
+            self.run_command(command);
         }
-        // Add matching text with highlighted formatting
-        job.append(
-            &message[mat.start()..mat.end()],
-            0.0,
-            TextFormat {
-                color: Color32::BLACK,
-                background: Color32::from_hex("#E69F00").unwrap(),
-                ..Default::default()
-            },
-        );
-        last_end = mat.end();
     }
 
-    if last_end < message.len() {
-        // Add remaining non-matching text
-        job.append(
-            &message[last_end..],
-            0.0,
-            TextFormat {
-                ..Default::default()
-            },
-        );
+    fn open_url(&mut self) {
+        let url = self.url.trim().to_string();
+        let name = url.rsplit('/').next().unwrap_or(&url).to_string();
+        let worker = parser::process_from_url(url, self.allowed_services(), self.parse_options());
+        self.start_loading(name, worker);
     }
-}
 
-fn create_layout_from_terminal_escape_sequence(input: &str, job: &mut LayoutJob) {
-    let mut current_format = TextFormat::default();
-
-    let mut chars = input.chars().peekable();
-    while let Some(c) = chars.next() {
-        if c == '\x1b' && chars.peek() == Some(&'[') {
-            // Consume '['
-            chars.next();
+    fn open_dropped_file(&mut self, file: egui::DroppedFile) {
+        let file_name = match &file.path {
+            Some(path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            None => file.name.clone(),
+        };
+        // Browsers hand over the content, native only gives the path
+        #[cfg(not(target_arch = "wasm32"))]
+        let from_path = file.path.map(parser::LogSource::Path);
+        #[cfg(target_arch = "wasm32")]
+        let from_path = None;
+        let source = file.bytes.map(parser::LogSource::Data).or(from_path);
+        let worker = match source {
+            Some(source) => parser::process_from_file(
+                source,
+                file_name.clone(),
+                self.allowed_services(),
+                self.parse_options(),
+            ),
+            None => parser::Worker::failed(format!("No content for dropped file {file_name}")),
+        };
+        self.start_loading(file_name, worker);
+    }
 
-            // Collect the entire escape code
-            let mut escape_code = String::new();
-            while let Some(&num) = chars.peek() {
-                if num.is_ascii_digit() || num == ';' {
-                    escape_code.push(chars.next().unwrap());
-                } else {
-                    break;
-                }
-            }
+    fn open_single_log(&mut self) {
+        let allowed_services = self.allowed_services();
+        let options = self.parse_options();
 
-            // Consume the final character ('m' in this case)
-            let _ = chars.next();
+        #[cfg(target_arch = "wasm32")]
+        let picked = {
+            let future = async move {
+                let Some(file) = rfd::AsyncFileDialog::new()
+                    .add_filter("Logs", parser::LOG_EXTENSIONS)
+                    .pick_file()
+                    .await
+                else {
+                    return None;
+                };
+                let source = parser::LogSource::Data(file.read().await.into());
+                let worker =
+                    parser::process_from_file(source, file.file_name(), allowed_services, options);
+                Some((file.file_name(), worker))
+            };
+            async_std::task::block_on(future)
+        };
 
-            for code in escape_code.split(';') {
-                match code {
-                    "0" => current_format = TextFormat::default(), // Reset
-                    "1" => current_format.underline = egui::Stroke::new(2.0, current_format.color), // Bold on (no bold in egui, use underline)
-                    "3" => current_format.italics = true, // Italic on
-                    "30" => current_format.color = Color32::BLACK,
-                    "31" => current_format.color = Color32::RED,
-                    "32" => current_format.color = Color32::GREEN,
-                    "33" => current_format.color = Color32::YELLOW,
-                    "34" => current_format.color = Color32::BLUE,
-                    "35" => current_format.color = Color32::from_hex("#FF00FF").unwrap(),
-                    "36" => current_format.color = Color32::from_hex("#00FFFF").unwrap(),
-                    "37" => current_format.color = Color32::WHITE,
-                    _ => (),
-                }
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut followed = None;
+        #[cfg(not(target_arch = "wasm32"))]
+        let picked = if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Logs", parser::LOG_EXTENSIONS)
+            .pick_file()
+        {
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            // Only plain text can be read from where it was left
+            if !parser::is_compressed(&file_name) && !file_name.ends_with(".zip") {
+                followed = Some(path.clone());
             }
+            let worker = parser::process_from_file(
+                parser::LogSource::Path(path),
+                file_name.clone(),
+                allowed_services,
+                options,
+            );
+            Some((file_name, worker))
         } else {
-            job.append(&c.to_string(), 0.0, current_format.clone());
+            None
+        };
+
+        if let Some((name, worker)) = picked {
+            self.start_loading(name, worker);
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                self.loading_path = followed;
+            }
         }
     }
 }
 
-impl TemplateApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        Default::default()
+impl eframe::App for TemplateApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        for (_, tab) in self.tree.iter_all_tabs() {
+            self.tab_filters
+                .insert(tab.title.clone(), TabFilter::from_tab(tab));
+            if let Some(bundle) = tab.bundle {
+                let notes = self.saved_notes.entry(bundle).or_default();
+                if tab.notes.is_empty() {
+                    notes.remove(&tab.title);
+                } else {
+                    notes.insert(tab.title.clone(), tab.notes.clone().into_iter().collect());
+                }
+            }
+        }
+        self.saved_notes.retain(|_, notes| !notes.is_empty());
+        // Titles of tabs of several bundles name their bundle, only a single one is kept
+        if let [_] = self.bundles.as_slice() {
+            let layout = self.tree.filter_map_tabs(|tab| {
+                let source = tab.source.clone()?;
+                let title = tab.title.clone();
+                Some(TabLayout { title, source })
+            });
+            self.layout = (layout.main_surface().num_tabs() > 0).then_some(layout);
+        }
+        eframe::set_value(storage, eframe::APP_KEY, self);
     }
-}
 
-impl eframe::App for TemplateApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.tab_viewer.palette = self.theme.apply(ctx, self.level_colors.as_ref());
         let modal = Modal::new(ctx, "my_modal");
-        let cloned_worker = self.worker.clone();
 
         // What goes inside the modal
         modal.show(|ui| {
@@ -420,9 +4252,37 @@ impl eframe::App for TemplateApp {
             // ui you want inside [`.show()`]
             modal.title(ui, "Open BlueOS Log file");
             modal.frame(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.service_filter)
+                        .hint_text("Filter services"),
+                );
+                let service_filter = self.service_filter.to_lowercase();
+                let mut visible_services: Vec<(&String, &mut bool)> = self
+                    .service_names
+                    .iter_mut()
+                    .filter(|(name, _)| self.discovered_services.contains(*name))
+                    .filter(|(name, _)| name.to_lowercase().contains(&service_filter))
+                    .collect();
+                ui.horizontal(|ui| {
+                    if ui.button("Select all").clicked() {
+                        visible_services
+                            .iter_mut()
+                            .for_each(|(_, checked)| **checked = true);
+                    }
+                    if ui.button("Select none").clicked() {
+                        visible_services
+                            .iter_mut()
+                            .for_each(|(_, checked)| **checked = false);
+                    }
+                    if ui.button("Invert").clicked() {
+                        visible_services
+                            .iter_mut()
+                            .for_each(|(_, checked)| **checked = !**checked);
+                    }
+                });
                 egui::ScrollArea::vertical().show(ui, |ui| {
                     ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
-                        for (service_name, checked) in &mut self.service_names {
+                        for (service_name, checked) in visible_services {
                             ui.checkbox(checked, service_name);
                             ui.end_row();
                         }
@@ -433,34 +4293,20 @@ impl eframe::App for TemplateApp {
                 // After clicking, the modal is automatically closed
                 if modal.button(ui, "close").clicked() {
                     self.open_model = false;
+                    self.pending_bundle = None;
+                    self.keep_bundles = false;
                 };
 
-                if modal.button(ui, "Load file").clicked() {
-                    let allowed_services: Vec<String> = self
-                        .service_names
-                        .iter()
-                        .filter(|(_, &value)| value)
-                        .map(|(name, _)| name)
-                        .cloned()
-                        .collect();
-                    #[cfg(target_arch = "wasm32")]
-                    {
-                        let future = async move {
-                            let file = rfd::AsyncFileDialog::new().pick_file().await;
-                            let data = file.unwrap().read().await;
-                            *cloned_worker.lock().unwrap() =
-                                parser::process_from_zip(data, allowed_services);
-                        };
-                        async_std::task::block_on(future);
-                    }
-
-                    #[cfg(not(target_arch = "wasm32"))]
-                    if let Some(path) = rfd::FileDialog::new().pick_file() {
-                        let data = std::fs::read(path).unwrap();
-                        *cloned_worker.lock().unwrap() =
-                            parser::process_from_zip(data, allowed_services);
+                if modal.button(ui, "Load").clicked() {
+                    if let Some(bundle) = self.pending_bundle.take() {
+                        self.last_bundle = Some(bundle.clone());
+                        let worker = parser::process_from_zip(
+                            bundle.source,
+                            self.allowed_services(),
+                            self.parse_options(),
+                        );
+                        self.start_loading(bundle.name, worker);
                     }
-
                     self.open_model = false;
                 }
             });
@@ -469,6 +4315,259 @@ impl eframe::App for TemplateApp {
         if self.open_model {
             modal.open();
         }
+        if let Some(file) = ctx
+            .input_mut(|i| std::mem::take(&mut i.raw.dropped_files))
+            .pop()
+        {
+            self.open_dropped_file(file);
+        }
+
+        self.command_palette_ui(ctx);
+        self.handle_shortcuts(ctx);
+        self.read_growing_bundles(ctx);
+        #[cfg(target_arch = "wasm32")]
+        self.share_view();
+        egui::Window::new("Keyboard shortcuts")
+            .open(&mut self.show_shortcuts)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("shortcuts").striped(true).show(ui, |ui| {
+                    for (keys, action) in [
+                        ("Ctrl+O", "Open a log bundle"),
+                        ("Ctrl+F", "Search in the active tab"),
+                        ("Ctrl+W", "Close the active tab"),
+                        ("Ctrl+P", "Open a service or run an action"),
+                        ("Shift+Click", "Open a service in a new window"),
+                        ("Ctrl+Click", "Open a service again, next to its open tab"),
+                        ("Escape", "Clear the filter of the active tab"),
+                        ("Enter / Shift+Enter", "Next / previous match"),
+                        ("F1", "Show this help"),
+                    ] {
+                        ui.strong(keys);
+                        ui.label(action);
+                        ui.end_row();
+                    }
+                });
+            });
+
+        let tab_titles: Vec<String> = self
+            .tree
+            .iter_all_tabs()
+            .map(|(_, tab)| tab.title.clone())
+            .collect();
+        let mut compare_open = self.compare.is_some();
+        let mut compared = false;
+        if let Some(compare) = &mut self.compare {
+            egui::Window::new("Compare tabs")
+                .open(&mut compare_open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    egui::Grid::new("compare").show(ui, |ui| {
+                        for (label, title) in
+                            [("Left", &mut compare.left), ("Right", &mut compare.right)]
+                        {
+                            ui.label(label);
+                            egui::ComboBox::from_id_source(label)
+                                .selected_text(title.as_str())
+                                .width(240.0)
+                                .show_ui(ui, |ui| {
+                                    for tab_title in &tab_titles {
+                                        ui.selectable_value(title, tab_title.clone(), tab_title);
+                                    }
+                                });
+                            ui.end_row();
+                        }
+                        ui.label("Window");
+                        ui.add(
+                            egui::DragValue::new(&mut compare.window_seconds)
+                                .clamp_range(0..=3600)
+                                .suffix(" s"),
+                        )
+                        .on_hover_text("Entries further apart than this are never paired");
+                        ui.end_row();
+                    });
+                    ui.checkbox(&mut compare.align_starts, "Align starts")
+                        .on_hover_text(
+                            "Count the time of each side from its first entry, \
+                             for logs of different vehicles or boots",
+                        );
+                    if ui
+                        .add_enabled(compare.left != compare.right, egui::Button::new("Compare"))
+                        .clicked()
+                    {
+                        compared = true;
+                    }
+                });
+        }
+        if compared {
+            if let Some(compare) = self.compare.take() {
+                let entries = |title: &String| {
+                    self.tree
+                        .iter_all_tabs()
+                        .find(|(_, tab)| &tab.title == title)
+                        .map_or(&[][..], |(_, tab)| &tab.entries[..])
+                };
+                self.diff = Some(DiffView::new(
+                    &compare,
+                    entries(&compare.left),
+                    entries(&compare.right),
+                ));
+            }
+        }
+        if !compare_open {
+            self.compare = None;
+        }
+
+        let mut diff_open = true;
+        if let Some(diff) = &mut self.diff {
+            egui::Window::new(format!("Diff {}", diff.title))
+                .id(egui::Id::new("diff"))
+                .open(&mut diff_open)
+                .default_size([900.0, 500.0])
+                .show(ctx, |ui| diff.ui(ui, &self.tab_viewer.palette));
+        }
+        if !diff_open {
+            self.diff = None;
+        }
+
+        egui::Window::new("Parsing profiles")
+            .open(&mut self.show_profiles)
+            .default_width(500.0)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Regexes tried in order on lines the stock formats do not match. \
+                     Named groups: timestamp and message, optionally level and component. \
+                     Changes apply to logs loaded afterwards.",
+                );
+                ui.separator();
+                let count = self.profiles.len();
+                let mut moved = None;
+                let mut removed = None;
+                for (index, profile) in self.profiles.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut profile.enabled, "");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut profile.name)
+                                .hint_text("Name")
+                                .desired_width(100.0),
+                        );
+                        if ui
+                            .add_enabled(index > 0, egui::Button::new("⏶"))
+                            .on_hover_text("Try earlier")
+                            .clicked()
+                        {
+                            moved = Some((index, index - 1));
+                        }
+                        if ui
+                            .add_enabled(index + 1 < count, egui::Button::new("⏷"))
+                            .on_hover_text("Try later")
+                            .clicked()
+                        {
+                            moved = Some((index, index + 1));
+                        }
+                        if ui.button("🗑").on_hover_text("Remove").clicked() {
+                            removed = Some(index);
+                        }
+                    });
+                    ui.add(
+                        egui::TextEdit::singleline(&mut profile.pattern)
+                            .code_editor()
+                            .hint_text(r"^(?P<timestamp>\S+ \S+) (?P<level>\w+) (?P<message>.*)$")
+                            .desired_width(f32::INFINITY),
+                    );
+                    if let Err(error) = profile.regex() {
+                        ui.label(
+                            RichText::new(error)
+                                .color(self.tab_viewer.palette.levels.get(&LogLevel::Error)),
+                        );
+                    }
+                    ui.separator();
+                }
+                if let Some((from, to)) = moved {
+                    self.profiles.swap(from, to);
+                }
+                if let Some(index) = removed {
+                    self.profiles.remove(index);
+                }
+                if ui.button("Add profile").clicked() {
+                    self.profiles.push(parser::Profile {
+                        name: format!("Profile {}", count + 1),
+                        pattern: Default::default(),
+                        enabled: true,
+                    });
+                }
+            });
+
+        let mut open_url_window = self.open_url_window;
+        egui::Window::new("Open from URL")
+            .open(&mut open_url_window)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.url)
+                            .hint_text("http://blueos.local/.../logs.zip")
+                            .desired_width(300.0),
+                    );
+                    let submitted =
+                        response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if (ui.button("Open").clicked() || submitted) && !self.url.trim().is_empty() {
+                        self.open_url();
+                        self.open_url_window = false;
+                    }
+                });
+            });
+        self.open_url_window &= open_url_window;
+
+        let mut open_live_window = self.open_live_window;
+        egui::Window::new("Connect to a vehicle")
+            .open(&mut open_live_window)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.live_polling, false, "WebSocket")
+                        .on_hover_text("Receive the lines as the vehicle sends them");
+                    ui.radio_value(&mut self.live_polling, true, "linux2rest")
+                        .on_hover_text(
+                            "Request the log of a service regularly and add the new entries",
+                        );
+                    if self.live_polling {
+                        ui.add(
+                            egui::DragValue::new(&mut self.poll_interval)
+                                .clamp_range(1.0..=600.0)
+                                .speed(0.5)
+                                .prefix("every ")
+                                .suffix(" s"),
+                        );
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let hint = if self.live_polling {
+                        "http://blueos.local/..."
+                    } else {
+                        "ws://blueos.local/..."
+                    };
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.live_url)
+                            .hint_text(hint)
+                            .desired_width(300.0),
+                    );
+                    let submitted =
+                        response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if (ui.button("Connect").clicked() || submitted)
+                        && !self.live_url.trim().is_empty()
+                    {
+                        self.connect_live();
+                        self.open_live_window = false;
+                    }
+                });
+            });
+        self.open_live_window &= open_live_window;
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             let now = chrono::prelude::Utc::now();
             let _delta = now - self.last_time;
@@ -477,39 +4576,288 @@ impl eframe::App for TemplateApp {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
                     if ui.button("Select a file").clicked() {
-                        self.open_model = true;
+                        ui.close_menu();
+                        self.select_bundle(false);
+                    }
+
+                    if ui
+                        .add_enabled(!self.bundles.is_empty(), egui::Button::new("Add a bundle"))
+                        .on_hover_text("Load another bundle next to the open ones, to compare them")
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.select_bundle(true);
+                    }
+
+                    if ui
+                        .add_enabled(self.last_bundle.is_some(), egui::Button::new("Reload"))
+                        .on_hover_text("Load the last bundle again, choosing its services")
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.reload_bundle();
+                    }
+
+                    if ui.button("Open single log").clicked() {
+                        ui.close_menu();
+                        self.open_single_log();
+                    }
 
+                    if ui.button("Open from URL").clicked() {
+                        ui.close_menu();
+                        self.open_url_window = true;
+                    }
+
+                    if ui
+                        .button("Connect to a vehicle")
+                        .on_hover_text("Show the logs of a vehicle as they come")
+                        .clicked()
+                    {
                         ui.close_menu();
-                        self.logs = Default::default();
-                        self.is_processing = true;
+                        self.open_live_window = true;
                     }
 
+                    ui.add_enabled_ui(!self.bundles.is_empty(), |ui| {
+                        ui.menu_button("Export all", |ui| {
+                            ui.radio_value(&mut self.export_level, None, "All levels");
+                            for level in
+                                LogLevel::iter().filter(|level| *level != LogLevel::Unknown)
+                            {
+                                let text = format!("{level} and above");
+                                ui.radio_value(&mut self.export_level, Some(level), text);
+                            }
+                            ui.separator();
+                            ui.checkbox(&mut self.export_date_range, "Only the date range")
+                                .on_hover_text("Keep to the date range chosen in the tabs");
+                            if ui
+                                .button("Save")
+                                .on_hover_text(
+                                    "Save every service merged in time order as a single text file",
+                                )
+                                .clicked()
+                            {
+                                ui.close_menu();
+                                self.export_all();
+                            }
+                        });
+                    });
+
                     let is_web = cfg!(target_arch = "wasm32");
                     if !is_web && ui.button("Quit").clicked() {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                     }
                 });
+                ui.menu_button("View", |ui| {
+                    ui.checkbox(&mut self.tab_viewer.show_deltas, "Time since previous row");
+                    ui.checkbox(&mut self.tab_viewer.show_histogram, "Level histogram");
+                    ui.checkbox(&mut self.tab_viewer.show_source_file, "Source file")
+                        .on_hover_text("Show the bundle file each entry was read from");
+                    ui.menu_button("Timestamp format", |ui| {
+                        let format = &mut self.tab_viewer.timestamp_format;
+                        for preset in TimestampFormat::PRESETS {
+                            let text = preset.to_string();
+                            ui.radio_value(format, preset, text);
+                        }
+                        let is_custom = matches!(format, TimestampFormat::Custom(_));
+                        if ui.radio(is_custom, "Custom").clicked() && !is_custom {
+                            *format =
+                                TimestampFormat::Custom(TimestampFormat::DEFAULT_CUSTOM.into());
+                        }
+                        if let TimestampFormat::Custom(custom) = format {
+                            ui.add(egui::TextEdit::singleline(custom).desired_width(160.0))
+                                .on_hover_text("chrono format string, e.g. %H:%M:%S%.3f");
+                            if !TimestampFormat::is_valid(custom) {
+                                ui.label(
+                                    RichText::new("Invalid format").color(
+                                        self.tab_viewer.palette.levels.get(&LogLevel::Error),
+                                    ),
+                                );
+                            }
+                        }
+                    });
+                    ui.menu_button("Palette", |ui| {
+                        for kind in PaletteKind::iter() {
+                            let text = kind.to_string();
+                            ui.radio_value(&mut self.theme.palette, kind, text);
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "Colorblind safe keeps warnings and errors apart by lightness too, \
+                         high contrast strengthens text and colors for dim or washed out displays",
+                    );
+                    ui.menu_button("Level colors", |ui| {
+                        let theme_colors = self.theme.palette().levels;
+                        let mut colors = self.level_colors.unwrap_or(theme_colors);
+                        egui::Grid::new("level_colors").show(ui, |ui| {
+                            for level in LogLevel::iter() {
+                                ui.label(level.to_string());
+                                egui::color_picker::color_edit_button_srgba(
+                                    ui,
+                                    colors.get_mut(&level),
+                                    egui::color_picker::Alpha::Opaque,
+                                );
+                                ui.end_row();
+                            }
+                        });
+                        if colors != theme_colors {
+                            self.level_colors = Some(colors);
+                        }
+                        if ui
+                            .add_enabled(self.level_colors.is_some(), egui::Button::new("Reset"))
+                            .on_hover_text("Use the colors of the theme")
+                            .clicked()
+                        {
+                            self.level_colors = None;
+                        }
+                    });
+                    ui.checkbox(&mut self.guess_unknown_levels, "Guess unknown levels")
+                        .on_hover_text(
+                            "Give entries without a known level the level their message \
+                             suggests, e.g. ERROR for \"panic\" or \"failed\", \
+                             applies to logs loaded afterwards",
+                        );
+                    ui.checkbox(&mut self.dedup_entries, "Remove duplicate entries")
+                        .on_hover_text(
+                            "Drop entries repeated with the same timestamp, level and message, \
+                             as where rotated logs overlap, applies to logs loaded afterwards",
+                        );
+                    ui.checkbox(&mut self.keep_unparsed, "Keep unparsed lines")
+                        .on_hover_text(
+                            "Show lines found before the first entry of a file as UNKNOWN \
+                             entries instead of dropping them, applies to logs loaded afterwards",
+                        );
+                    if ui.button("Parsing profiles").clicked() {
+                        ui.close_menu();
+                        self.show_profiles = true;
+                    }
+                    if ui
+                        .checkbox(&mut self.notify_when_loaded, "Notify when loaded")
+                        .on_hover_text(
+                            "Flash the window or send a notification once a bundle is loaded",
+                        )
+                        .changed()
+                        && self.notify_when_loaded
+                    {
+                        request_notifications();
+                    }
+                    if ui
+                        .add_enabled(tab_titles.len() > 1, egui::Button::new("Compare tabs"))
+                        .on_hover_text("Show the entries found in only one of two tabs")
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.compare = Some(Compare {
+                            left: tab_titles[0].clone(),
+                            right: tab_titles[1].clone(),
+                            window_seconds: 5,
+                            align_starts: self.bundles.len() > 1,
+                        });
+                    }
+                    ui.separator();
+                    let focused = self
+                        .tree
+                        .find_active_focused()
+                        .map(|(_, tab)| tab.title.clone());
+                    if ui
+                        .add_enabled(focused.is_some(), egui::Button::new("Close other tabs"))
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        if let Some(title) = focused {
+                            self.close_tabs(CloseTabs::Others(title));
+                        }
+                    }
+                    if ui
+                        .add_enabled(!tab_titles.is_empty(), egui::Button::new("Close all tabs"))
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.close_tabs(CloseTabs::All);
+                    }
+                    ui.separator();
+                    let has_logs = !self.bundles.is_empty();
+                    if ui
+                        .add_enabled(has_logs, egui::Button::new("Error summary"))
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.open_error_summary(false);
+                    }
+                    if ui
+                        .add_enabled(has_logs, egui::Button::new("Error and warning summary"))
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.open_error_summary(true);
+                    }
+                });
+                ui.menu_button("Help", |ui| {
+                    if ui.button("Keyboard shortcuts").clicked() {
+                        ui.close_menu();
+                        self.show_shortcuts = true;
+                    }
+                });
                 ui.add_space(16.0);
 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
-                    egui::widgets::global_dark_light_mode_switch(ui);
+                    if let Some(visuals) = ctx.style().visuals.light_dark_small_toggle_button(ui) {
+                        self.theme.dark_mode = visuals.dark_mode;
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.tab_viewer.linked_scrolling, "Linked scrolling")
+                        .on_hover_text("Scroll every tab to the row clicked in another tab");
+                    ui.separator();
+                    ui.add(
+                        egui::DragValue::new(&mut self.tab_viewer.font_size)
+                            .clamp_range(8.0..=32.0)
+                            .speed(0.1)
+                            .suffix(" pt"),
+                    )
+                    .on_hover_text("Size of the log text");
+                    ui.checkbox(&mut self.tab_viewer.monospace, "Monospace")
+                        .on_hover_text("Show the log text in a monospace font");
                     ui.separator();
-                    if self.is_processing {
-                        if let Some(info) = self.worker.lock().unwrap().info() {
-                            ui.label(format!(
-                                "Processing: {}   {:.2}% {}",
+                    let worker = self.worker.lock().unwrap().clone();
+                    if let Some(error) = worker.error() {
+                        self.is_processing = false;
+                        ui.label(RichText::new(error).color(Color32::RED));
+                    } else if self.is_processing {
+                        if ui.button("Cancel").clicked() {
+                            worker.cancel();
+                            self.is_processing = false;
+                        }
+                        ui.spinner();
+                        if let Some(info) = worker.info() {
+                            let mut text = format!(
+                                "Processing {}: {}   {:.2}% of {}, {} parsed",
+                                info.service_name,
                                 info.file,
                                 info.percentage,
+                                &bytesize::ByteSize(info.total as u64).to_string(),
                                 &bytesize::ByteSize(info.size as u64).to_string(),
-                            ));
+                            );
+                            if let Some(remaining) = info.remaining() {
+                                let seconds = remaining.num_seconds().max(1) as u64;
+                                text += &format!(
+                                    ", ~{} remaining",
+                                    humantime::format_duration(std::time::Duration::from_secs(
+                                        seconds
+                                    ))
+                                );
+                            }
+                            ui.label(text);
                         }
                     } else {
-                        let size = self.logs.size;
+                        let size: usize = self.bundles.iter().map(|bundle| bundle.logs.size).sum();
+                        let duration = self
+                            .bundles
+                            .last()
+                            .map_or(chrono::TimeDelta::zero(), |bundle| bundle.logs.duration);
                         ui.label(format!(
                             "{} [{}]",
                             &bytesize::ByteSize(size as u64).to_string(),
-                            humantime::format_duration(self.logs.duration.to_std().unwrap())
-                                .to_string()
+                            humantime::format_duration(duration.to_std().unwrap())
                         ));
                     }
                 });
@@ -523,50 +4871,278 @@ impl eframe::App for TemplateApp {
                 ui.vertical_centered(|ui| {
                     ui.heading("Services");
                 });
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.service_search)
+                        .hint_text("Search services"),
+                );
 
                 egui::ScrollArea::vertical().show(ui, |ui| {
                     ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
-                        if self.logs.logbook.is_empty() {
-                            if let Some(p) = self.worker.lock().unwrap().processed() {
-                                self.logs = p;
+                        if self.is_processing {
+                            let worker = self.worker.lock().unwrap().clone();
+                            if let Some(processed) = worker.processed() {
+                                self.is_processing = false;
+                                self.add_bundle(processed);
+                                if self.notify_when_loaded {
+                                    let name = self.bundles.last().map(|bundle| &bundle.name);
+                                    notify_loaded(ui.ctx(), name.cloned().unwrap_or_default());
+                                }
+                            } else if let Some(services) = worker.discovered() {
                                 self.is_processing = false;
+                                self.show_discovered_services(services);
                             }
                         }
 
-                        self.logs.logbook.iter().for_each(|(service, entries)| {
-                            if ui.button(service).clicked() {
-                                if self.tree.main_surface().num_tabs() == 0 {
-                                    let tab = TabContent::new(service.clone(), entries.clone());
-                                    self.tree.main_surface_mut().push_to_first_leaf(tab);
-                                } else {
-                                    let mut tab_name = service.clone();
-
-                                    while self
-                                        .tree
-                                        .iter_all_tabs()
-                                        .any(|(_data, tab)| *tab.title == tab_name)
-                                    {
-                                        tab_name += "+"
-                                    }
-                                    let tab = TabContent::new(tab_name, entries.clone());
-                                    self.tree.add_window(vec![tab]);
+                        let several = self.bundles.len() > 1;
+                        let range = (self.tab_viewer.first_date, self.tab_viewer.second_date);
+                        let mut opened = vec![];
+                        let mut closed = None;
+                        for (index, bundle) in self.bundles.iter_mut().enumerate() {
+                            if several {
+                                egui::CollapsingHeader::new(&bundle.name)
+                                    .id_source(("bundle", index))
+                                    .default_open(true)
+                                    .show(ui, |ui| {
+                                        if ui.button("Close bundle").clicked() {
+                                            closed = Some(index);
+                                        }
+                                        opened.extend(bundle.ui(
+                                            ui,
+                                            several,
+                                            &self.service_search,
+                                            range,
+                                            &self.tab_viewer.palette,
+                                        ));
+                                    });
+                            } else {
+                                opened.extend(bundle.ui(
+                                    ui,
+                                    several,
+                                    &self.service_search,
+                                    range,
+                                    &self.tab_viewer.palette,
+                                ));
+                            }
+                        }
+                        if let Some(index) = closed {
+                            self.bundles.remove(index);
+                        }
+                        let (in_window, another_view) =
+                            ui.input(|i| (i.modifiers.shift, i.modifiers.command));
+                        for mut tab in opened {
+                            // Keeps the open tab and opens a second one next to it
+                            if another_view {
+                                while find_tab(&self.tree, &tab.title).is_some() {
+                                    tab.title += "+";
                                 }
                             }
-                        });
+                            open_tab(
+                                &mut self.tree,
+                                &self.tab_filters,
+                                &self.saved_notes,
+                                tab,
+                                in_window,
+                            );
+                        }
                     });
                 });
 
                 ui.separator();
             });
 
+        self.inspector_ui(ctx);
+
         egui::CentralPanel::default().show(ctx, |_ui| {
             DockArea::new(&mut self.tree)
                 .style(Style::from_egui(ctx.style().as_ref()))
                 .show(ctx, &mut self.tab_viewer);
         });
+        if let Some(close) = self.tab_viewer.close_tabs.take() {
+            self.close_tabs(close);
+        }
 
         if self.is_processing {
             ctx.request_repaint();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use egui_dock::TabViewer as _;
+
+    fn entry(seconds: i64, level: LogLevel, message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: "2024-03-12T10:00:00Z".parse::<DateTime<Utc>>().unwrap()
+                + chrono::TimeDelta::seconds(seconds),
+            level,
+            component: None,
+            message: message.into(),
+            service: None,
+            traceback: None,
+            source_file: None,
+            note: None,
+            lines: 1,
+            raw: message.into(),
+        }
+    }
+
+    fn large_tab(count: usize) -> TabContent {
+        let levels = [
+            LogLevel::Info,
+            LogLevel::Debug,
+            LogLevel::Warn,
+            LogLevel::Error,
+        ];
+        let events = [
+            "heartbeat",
+            "armed",
+            "disarmed",
+            "timeout",
+            "mission",
+            "battery",
+        ];
+        let entries = (0..count)
+            .map(|index| {
+                let message = format!(
+                    "message {index} from component{} with {} value={}",
+                    index % 50,
+                    events[index % events.len()],
+                    index % 7
+                );
+                entry(index as i64, levels[index % levels.len()].clone(), &message)
+            })
+            .collect();
+        TabContent::new("large".into(), Arc::new(entries))
+    }
+
+    // Average milliseconds of the frames showing the tab, recomputing its rows each time if dirty
+    fn frame_time(viewer: &mut TabViewer, tab: &mut TabContent, frames: usize, dirty: bool) -> f64 {
+        let ctx = egui::Context::default();
+        let frame = |viewer: &mut TabViewer, tab: &mut TabContent| {
+            let _ = ctx.run(egui::RawInput::default(), |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| viewer.ui(ui, tab));
+            });
+        };
+        frame(viewer, tab);
+        let started = std::time::Instant::now();
+        for _ in 0..frames {
+            tab.dirty |= dirty;
+            frame(viewer, tab);
+        }
+        started.elapsed().as_secs_f64() * 1000.0 / frames as f64
+    }
+
+    fn filter(tab: &mut TabContent, filter: &str, use_regex: bool) {
+        tab.filter = filter.into();
+        tab.use_regex = use_regex;
+        tab.is_search = false;
+        tab.dirty = true;
+    }
+
+    #[test]
+    fn date_range_includes_its_bounds() {
+        let day = chrono::NaiveDate::from_ymd_opt(2024, 3, 12).unwrap();
+        let first = day.and_hms_opt(10, 0, 0).unwrap().and_utc();
+        let second = day.and_time(end_of_minute(10, 5)).and_utc();
+        let at = |text: &str| text.parse::<DateTime<Utc>>().unwrap();
+
+        assert!(in_date_range(at("2024-03-12T10:00:00Z"), first, second));
+        assert!(in_date_range(
+            at("2024-03-12T10:05:59.999999999Z"),
+            first,
+            second
+        ));
+        assert!(!in_date_range(
+            at("2024-03-12T09:59:59.999999999Z"),
+            first,
+            second
+        ));
+        assert!(!in_date_range(at("2024-03-12T10:06:00Z"), first, second));
+    }
+
+    #[test]
+    fn index_agrees_with_scan_on_timestamp_words() {
+        let mut tab = large_tab(10);
+        let index = SearchIndex::new(&tab.entries);
+        assert!(index.lookup("utc").is_none());
+        assert!(index.lookup("tc").is_none());
+        assert!(index.lookup("component").is_some());
+
+        let mut viewer = TabViewer::default();
+        for (term, expected) in [("utc", 10), ("UTC", 10), ("component3", 1), ("nothing", 0)] {
+            filter(&mut tab, term, false);
+            frame_time(&mut viewer, &mut tab, 0, false);
+            assert_eq!(tab.filtered.len(), expected, "{term}");
+        }
+    }
+
+    #[test]
+    fn download_exports_the_filtered_entries() {
+        let entries = vec![
+            entry(0, LogLevel::Info, "connected"),
+            entry(1, LogLevel::Error, "\x1b[31mfailed\x1b[0m to arm"),
+            entry(2, LogLevel::Info, "armed"),
+            entry(3, LogLevel::Error, "failed to disarm"),
+        ];
+        let mut tab = TabContent::new("export".into(), Arc::new(entries));
+        tab.notes
+            .insert(EntryId::new(&tab.entries[3]), "after landing".into());
+        filter(&mut tab, "failed", false);
+        frame_time(&mut TabViewer::default(), &mut tab, 0, false);
+
+        let start = tab.entries[0].timestamp;
+        let exported = export_entries(
+            FilteredEntries::new(&tab.entries, &tab.filtered).iter(),
+            &tab.notes,
+            ExportFormat::Txt,
+            true,
+            &TimestampFormat::Relative,
+            start,
+        );
+        let text = String::from_utf8(exported).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3, "{text}");
+        assert!(lines[0].ends_with("\tERROR\t\tfailed to arm"), "{text}");
+        assert!(lines[1].ends_with("\tERROR\t\tfailed to disarm"), "{text}");
+        assert_eq!(lines[2], "\tNote: after landing");
+    }
+
+    #[test]
+    fn bundle_hash_is_stable() {
+        let entries = vec![
+            entry(0, LogLevel::Info, "started"),
+            entry(60, LogLevel::Error, "stopped"),
+        ];
+        let logbook = parser::LogBook::from([("autopilot".to_string(), Arc::new(entries))]);
+        // Saved with the notes, changing it loses them
+        assert_eq!(bundle_hash(&logbook), 13653690691564834711);
+        assert_ne!(bundle_hash(&logbook), bundle_hash(&parser::LogBook::new()));
+    }
+
+    // cargo test --release -- --ignored --nocapture bench_search_index
+    #[test]
+    #[ignore]
+    fn bench_search_index() {
+        let mut tab = large_tab(200_000);
+        let mut viewer = TabViewer::default();
+        // Terms with digits are not indexed
+        filter(&mut tab, "timeout", false);
+        let indexed = frame_time(&mut viewer, &mut tab, 20, true);
+        filter(&mut tab, "timeout", true);
+        let scanned = frame_time(&mut viewer, &mut tab, 20, true);
+        println!("200k entries: {indexed:.1} ms with the index, {scanned:.1} ms scanning");
+    }
+
+    // cargo test --release -- --ignored --nocapture bench_dirty_flag
+    #[test]
+    #[ignore]
+    fn bench_dirty_flag() {
+        let mut tab = large_tab(200_000);
+        let mut viewer = TabViewer::default();
+        let clean = frame_time(&mut viewer, &mut tab, 20, false);
+        let dirty = frame_time(&mut viewer, &mut tab, 20, true);
+        println!("200k entries: {clean:.1} ms per clean frame, {dirty:.1} ms recomputing the rows");
+    }
+}
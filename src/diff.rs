@@ -0,0 +1,118 @@
+use crate::parser::{self, LogEntry};
+use chrono::{DateTime, TimeDelta, Utc};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Change {
+    Same,
+    // Same kind of message with different values, e.g. another voltage or PID
+    Changed,
+    Removed,
+    Added,
+}
+
+pub struct DiffRow {
+    // Time since the start of the compared logs
+    pub offset: TimeDelta,
+    pub change: Change,
+    pub left: Option<LogEntry>,
+    pub right: Option<LogEntry>,
+}
+
+// Pairs messages that only differ in their values
+fn shape(entry: &LogEntry) -> (Option<&str>, String) {
+    (
+        entry.component.as_deref(),
+        parser::message_pattern(&entry.message),
+    )
+}
+
+fn start(entries: &[LogEntry]) -> Option<DateTime<Utc>> {
+    entries.iter().map(|entry| entry.timestamp).min()
+}
+
+// Pairs entries of both sides whose timestamps are at most `window` apart, timestamps of
+// different vehicles never match exactly. With `align_starts` each side counts from its own
+// first entry, otherwise both count from the earliest one.
+pub fn diff(
+    left: &[LogEntry],
+    right: &[LogEntry],
+    window: TimeDelta,
+    align_starts: bool,
+) -> Vec<DiffRow> {
+    let (left_start, right_start) = match (start(left), start(right)) {
+        (Some(left), Some(right)) if align_starts => (left, right),
+        (left, right) => {
+            let start = left.into_iter().chain(right).min().unwrap_or_default();
+            (start, start)
+        }
+    };
+    let offsets = |entries: &[LogEntry], start: DateTime<Utc>| {
+        let mut offsets: Vec<(TimeDelta, usize)> = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| (entry.timestamp - start, index))
+            .collect();
+        offsets.sort();
+        offsets
+    };
+    let left_offsets = offsets(left, left_start);
+    let right_offsets = offsets(right, right_start);
+
+    let mut rows = vec![];
+    let mut matched = vec![false; right_offsets.len()];
+    let mut first = 0;
+    for &(offset, index) in &left_offsets {
+        let entry = &left[index];
+        while first < right_offsets.len() && right_offsets[first].0 < offset - window {
+            first += 1;
+        }
+        let candidates = || {
+            right_offsets[first..]
+                .iter()
+                .enumerate()
+                .take_while(|(_, (other, _))| *other <= offset + window)
+                .filter(|(position, _)| !matched[first + position])
+                .map(|(position, &(other, index))| {
+                    (first + position, (other - offset).abs(), &right[index])
+                })
+        };
+        let closest = |change: Change| {
+            candidates()
+                .filter(|(_, _, other)| match change {
+                    Change::Same => other.message == entry.message,
+                    _ => shape(other) == shape(entry),
+                })
+                .min_by_key(|(_, distance, _)| *distance)
+                .map(|(position, _, other)| (position, change, other.clone()))
+        };
+        match closest(Change::Same).or_else(|| closest(Change::Changed)) {
+            Some((position, change, other)) => {
+                matched[position] = true;
+                rows.push(DiffRow {
+                    offset,
+                    change,
+                    left: Some(entry.clone()),
+                    right: Some(other),
+                });
+            }
+            None => rows.push(DiffRow {
+                offset,
+                change: Change::Removed,
+                left: Some(entry.clone()),
+                right: None,
+            }),
+        }
+    }
+    for (position, &(offset, index)) in right_offsets.iter().enumerate() {
+        if !matched[position] {
+            rows.push(DiffRow {
+                offset,
+                change: Change::Added,
+                left: None,
+                right: Some(right[index].clone()),
+            });
+        }
+    }
+    rows.sort_by_key(|row| row.offset);
+    rows
+}
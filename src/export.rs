@@ -0,0 +1,161 @@
+use crate::ansi;
+use crate::parser::LogEntry;
+use crate::timestamp::TimestampFormat;
+use chrono::{DateTime, Utc};
+use strum_macros::EnumIter;
+
+#[derive(Clone, Copy, Debug, EnumIter, PartialEq)]
+pub enum ExportFormat {
+    Txt,
+    Csv,
+    Json,
+}
+
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportFormat::Txt => write!(f, "TXT"),
+            ExportFormat::Csv => write!(f, "CSV"),
+            ExportFormat::Json => write!(f, "JSON"),
+        }
+    }
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Txt => "txt",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    // Timestamps are formatted for text and CSV, JSON keeps them structured
+    pub fn export(
+        &self,
+        entries: &[LogEntry],
+        strip_ansi: bool,
+        timestamps: &TimestampFormat,
+        start: DateTime<Utc>,
+    ) -> String {
+        let stripped: Vec<LogEntry>;
+        let entries = if strip_ansi {
+            stripped = entries
+                .iter()
+                .map(|entry| LogEntry {
+                    message: ansi::strip_ansi(&entry.message),
+                    traceback: entry.traceback.as_deref().map(ansi::strip_ansi),
+                    ..entry.clone()
+                })
+                .collect();
+            &stripped
+        } else {
+            entries
+        };
+
+        match self {
+            ExportFormat::Txt => entries_to_text(entries, timestamps, start),
+            ExportFormat::Csv => entries_to_csv(entries, timestamps, start),
+            ExportFormat::Json => entries_to_json(entries),
+        }
+    }
+}
+
+pub fn entry_to_text(
+    entry: &LogEntry,
+    timestamps: &TimestampFormat,
+    start: DateTime<Utc>,
+) -> String {
+    let mut text = timestamps.format(entry.timestamp, start) + "\t";
+    // Entries of merged services tell where they came from
+    if let Some(service) = &entry.service {
+        text += &format!("{service}\t");
+    }
+    text += &format!(
+        "{}\t{}\t{}",
+        entry.level,
+        entry.component.as_deref().unwrap_or_default(),
+        entry.full_message()
+    );
+    match &entry.note {
+        Some(note) => format!("{text}\n\tNote: {note}"),
+        None => text,
+    }
+}
+
+pub fn entries_to_text(
+    entries: &[LogEntry],
+    timestamps: &TimestampFormat,
+    start: DateTime<Utc>,
+) -> String {
+    entries
+        .iter()
+        .map(|entry| entry_to_text(entry, timestamps, start) + "\n")
+        .collect()
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn entries_to_csv(
+    entries: &[LogEntry],
+    timestamps: &TimestampFormat,
+    start: DateTime<Utc>,
+) -> String {
+    let mut csv = String::from("timestamp,level,component,message,note\n");
+    for entry in entries {
+        // Spreadsheets read RFC 3339 better than the full display format
+        let timestamp = match timestamps {
+            TimestampFormat::Full => entry.timestamp.to_rfc3339(),
+            timestamps => timestamps.format(entry.timestamp, start),
+        };
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            csv_field(&timestamp),
+            entry.level,
+            csv_field(entry.component.as_deref().unwrap_or_default()),
+            csv_field(&entry.full_message()),
+            csv_field(entry.note.as_deref().unwrap_or_default())
+        ));
+    }
+    csv
+}
+
+pub fn entries_to_json(entries: &[LogEntry]) -> String {
+    serde_json::to_string_pretty(entries).unwrap_or_else(|e| {
+        log::error!("Failed to serialize entries: {:#?}", e);
+        "[]".into()
+    })
+}
+
+pub fn download_file(file_name: &str, data: Vec<u8>) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let file_name = file_name.to_string();
+        let future = async move {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .set_file_name(&file_name)
+                .save_file()
+                .await
+            else {
+                return;
+            };
+            if let Err(e) = file.write(&data).await {
+                log::error!("Failed to download {}: {:#?}", file_name, e);
+            }
+        };
+        async_std::task::block_on(future);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = rfd::FileDialog::new().set_file_name(file_name).save_file() {
+        if let Err(e) = std::fs::write(&path, data) {
+            log::error!("Failed to save {}: {:#?}", path.display(), e);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// How well the characters of the query appear in order in the candidate, none when some don't.
+// Consecutive characters and ones starting a word score higher, skipped characters lower.
+pub fn score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..candidate.len())
+            .find(|&index| candidate[index].to_lowercase().eq(wanted.to_lowercase()))?;
+        score += 1;
+        if found == 0 || !candidate[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        match previous {
+            Some(previous) if previous + 1 == found => score += 5,
+            Some(previous) => score -= (found - previous - 1).min(3) as i64,
+            None => score -= found.min(3) as i64,
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+// Candidates matching the query, best first and shorter first among equals
+pub fn filter<T>(
+    query: &str,
+    candidates: impl IntoIterator<Item = T>,
+    text: impl Fn(&T) -> &str,
+) -> Vec<T> {
+    if query.trim().is_empty() {
+        return candidates.into_iter().collect();
+    }
+    let mut matches: Vec<(i64, usize, T)> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let text = text(&candidate);
+            let length = text.chars().count();
+            score(query, text).map(|score| (score, length, candidate))
+        })
+        .collect();
+    matches.sort_by_key(|(score, length, _)| (std::cmp::Reverse(*score), *length));
+    matches
+        .into_iter()
+        .map(|(_, _, candidate)| candidate)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMANDS: [&str; 4] = ["Settings", "Toggle log", "Tab list", "Total"];
+
+    #[test]
+    fn filter_orders_best_first() {
+        // Word starts beat letters inside words, then the shorter candidate wins a tie
+        assert_eq!(
+            filter("tl", COMMANDS, |command| command),
+            vec!["Tab list", "Total", "Toggle log"]
+        );
+        assert_eq!(filter(" ", COMMANDS, |command| command), COMMANDS);
+    }
+
+    #[test]
+    fn score_needs_every_character_in_order() {
+        assert!(score("tgl", "Toggle").is_some());
+        assert_eq!(score("elg", "Toggle"), None);
+        assert_eq!(score("toggles", "Toggle"), None);
+        assert_eq!(score("xyz", "Toggle"), None);
+    }
+
+    #[test]
+    fn score_ignores_case() {
+        assert_eq!(score("TAB", "tab list"), score("tab", "Tab List"));
+        assert_eq!(
+            filter("TL", COMMANDS, |command| command),
+            filter("tl", COMMANDS, |command| command)
+        );
+    }
+}
@@ -1,6 +1,13 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+mod ansi;
 mod app;
 pub use app::TemplateApp;
 
+mod diff;
+mod export;
+mod fuzzy;
+mod live;
 mod parser;
+mod share;
+mod timestamp;
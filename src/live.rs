@@ -0,0 +1,268 @@
+use crate::parser::{self, LogEntry, ParseOptions};
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::io::BufReader;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(target_arch = "wasm32")]
+use tokio_with_wasm::tokio_wasm as tokio;
+
+// Service the streamed entries are shown as
+pub const SERVICE: &str = "live";
+
+// Entries received but not shown yet, the oldest are dropped beyond this when the viewer falls behind
+const MAX_PENDING: usize = 100_000;
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Debug)]
+pub enum LiveState {
+    Connecting,
+    Connected,
+    // Why the connection was lost, it is tried again after a while
+    Reconnecting(String),
+}
+
+impl std::fmt::Display for LiveState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LiveState::Connecting => write!(f, "Connecting"),
+            LiveState::Connected => write!(f, "Connected"),
+            LiveState::Reconnecting(error) => write!(f, "Reconnecting ({error})"),
+        }
+    }
+}
+
+struct Received {
+    state: LiveState,
+    entries: Vec<LogEntry>,
+    dropped: usize,
+}
+
+// Log lines streamed from a vehicle over a WebSocket or polled from its linux2rest endpoints
+#[derive(Clone)]
+pub struct Live {
+    pub url: String,
+    received: Arc<Mutex<Received>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Live {
+    fn new(url: String) -> Self {
+        Live {
+            url,
+            received: Arc::new(Mutex::new(Received {
+                state: LiveState::Connecting,
+                entries: vec![],
+                dropped: 0,
+            })),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn connect(url: String, options: ParseOptions) -> Self {
+        let live = Live::new(url);
+        stream(live.clone(), options);
+        live
+    }
+
+    pub fn poll(url: String, interval: Duration, options: ParseOptions) -> Self {
+        let live = Live::new(url);
+        poll(live.clone(), interval, options);
+        live
+    }
+
+    pub fn disconnect(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn state(&self) -> LiveState {
+        self.received.lock().unwrap().state.clone()
+    }
+
+    fn set_state(&self, state: LiveState) {
+        self.received.lock().unwrap().state = state;
+    }
+
+    // Entries dropped so far because they came faster than they were shown
+    pub fn dropped(&self) -> usize {
+        self.received.lock().unwrap().dropped
+    }
+
+    // Entries received since the last call
+    pub fn take_entries(&self) -> Vec<LogEntry> {
+        std::mem::take(&mut self.received.lock().unwrap().entries)
+    }
+
+    fn receive(&self, text: &str, options: &ParseOptions) {
+        self.push(parse(text, options));
+    }
+
+    fn push(&self, entries: Vec<LogEntry>) {
+        if entries.is_empty() {
+            return;
+        }
+        let mut received = self.received.lock().unwrap();
+        received.entries.extend(entries);
+        let excess = received.entries.len().saturating_sub(MAX_PENDING);
+        if excess > 0 {
+            received.entries.drain(..excess);
+            received.dropped += excess;
+        }
+    }
+}
+
+fn parse(text: &str, options: &ParseOptions) -> Vec<LogEntry> {
+    parser::process_log_file(BufReader::new(text.as_bytes()), options)
+        .map(|(entries, _, _)| entries)
+        .unwrap_or_default()
+}
+
+// linux2rest answers with the whole log every time, the entries already seen are left out
+fn poll(live: Live, interval: Duration, options: ParseOptions) {
+    let future = async move {
+        let mut seen: HashSet<(DateTime<Utc>, String)> = HashSet::new();
+        while !live.is_cancelled() {
+            match fetch(&live.url).await {
+                Ok(text) => {
+                    live.set_state(LiveState::Connected);
+                    let entries = parse(&text, &options)
+                        .into_iter()
+                        .filter(|entry| seen.insert((entry.timestamp, entry.message.clone())))
+                        .collect();
+                    live.push(entries);
+                }
+                Err(e) => live.set_state(LiveState::Reconnecting(e.to_string())),
+            }
+            tokio::time::sleep(interval).await;
+        }
+    };
+
+    // reqwest futures are not Send on the web
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(future);
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::spawn(future);
+}
+
+async fn fetch(url: &str) -> reqwest::Result<String> {
+    reqwest::get(url).await?.error_for_status()?.text().await
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn stream(live: Live, options: ParseOptions) {
+    use futures_util::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    tokio::spawn(async move {
+        let mut delay = MIN_RECONNECT_DELAY;
+        while !live.is_cancelled() {
+            live.set_state(LiveState::Connecting);
+            match tokio_tungstenite::connect_async(live.url.as_str()).await {
+                Ok((mut socket, _)) => {
+                    live.set_state(LiveState::Connected);
+                    delay = MIN_RECONNECT_DELAY;
+                    let mut error = "Connection closed".to_string();
+                    // Disconnecting is noticed with the next message
+                    while let Some(message) = socket.next().await {
+                        if live.is_cancelled() {
+                            let _ = socket.close(None).await;
+                            return;
+                        }
+                        match message {
+                            Ok(Message::Text(text)) => live.receive(&text, &options),
+                            Ok(Message::Binary(data)) => {
+                                live.receive(&String::from_utf8_lossy(&data), &options)
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                error = e.to_string();
+                                break;
+                            }
+                        }
+                    }
+                    live.set_state(LiveState::Reconnecting(error));
+                }
+                Err(e) => live.set_state(LiveState::Reconnecting(e.to_string())),
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+fn stream(live: Live, options: ParseOptions) {
+    connect_socket(live, options, MIN_RECONNECT_DELAY);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn connect_socket(live: Live, options: ParseOptions, delay: Duration) {
+    use wasm_bindgen::{closure::Closure, JsCast};
+    use web_sys::{CloseEvent, MessageEvent, WebSocket};
+
+    if live.is_cancelled() {
+        return;
+    }
+    live.set_state(LiveState::Connecting);
+    let socket = match WebSocket::new(&live.url) {
+        Ok(socket) => socket,
+        Err(e) => {
+            live.set_state(LiveState::Reconnecting(format!("{e:?}")));
+            return;
+        }
+    };
+
+    let onopen = {
+        let live = live.clone();
+        Closure::<dyn FnMut()>::new(move || live.set_state(LiveState::Connected))
+    };
+    socket.set_onopen(Some(onopen.as_ref().unchecked_ref()));
+    onopen.forget();
+
+    let onmessage = {
+        let live = live.clone();
+        let options = options.clone();
+        let socket = socket.clone();
+        Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+            if live.is_cancelled() {
+                let _ = socket.close();
+                return;
+            }
+            if let Some(text) = event.data().as_string() {
+                live.receive(&text, &options);
+            }
+        })
+    };
+    socket.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+    onmessage.forget();
+
+    let onclose = Closure::<dyn FnMut(CloseEvent)>::new(move |event: CloseEvent| {
+        if live.is_cancelled() {
+            return;
+        }
+        // A connection that was up starts over with the shortest delay
+        let delay = match live.state() {
+            LiveState::Connected => MIN_RECONNECT_DELAY,
+            _ => delay,
+        };
+        let reason = match event.reason() {
+            reason if reason.is_empty() => "Connection closed".to_string(),
+            reason => reason,
+        };
+        live.set_state(LiveState::Reconnecting(reason));
+        let (live, options) = (live.clone(), options.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            tokio::time::sleep(delay).await;
+            connect_socket(live, options, (delay * 2).min(MAX_RECONNECT_DELAY));
+        });
+    });
+    socket.set_onclose(Some(onclose.as_ref().unchecked_ref()));
+    onclose.forget();
+}
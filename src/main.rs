@@ -1,9 +1,6 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
-#[cfg(not(target_arch = "wasm32"))]
-use tokio;
-
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 #[tokio::main]
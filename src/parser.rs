@@ -1,13 +1,14 @@
 use chrono::{DateTime, Utc};
-use dateparser;
 use flate2::read::GzDecoder;
 use regex::Regex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::{
-    collections::BTreeMap,
-    env,
-    fs::File,
-    io::{self, BufRead, BufReader, Read, Seek},
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, BinaryHeap},
+    future::Future,
+    io::{self, BufRead, BufReader, Read, Seek, Write},
+    pin::Pin,
 };
 use strum_macros::EnumIter;
 use zip::ZipArchive;
@@ -15,19 +16,23 @@ use zip::ZipArchive;
 #[cfg(target_arch = "wasm32")]
 use tokio_with_wasm::tokio_wasm as tokio;
 
-#[cfg(not(target_arch = "wasm32"))]
-use tokio;
-
-use std::sync::Once;
+use std::sync::OnceLock;
 
-static mut REGEX_GENERAL: Option<Regex> = None;
-static mut REGEX_DETAILED: Option<Regex> = None;
-static INIT: Once = Once::new();
+static REGEX_GENERAL: OnceLock<Regex> = OnceLock::new();
+static REGEX_DETAILED: OnceLock<Regex> = OnceLock::new();
+static REGEX_RESTART: OnceLock<Regex> = OnceLock::new();
+static REGEX_ERROR_WORDS: OnceLock<Regex> = OnceLock::new();
+static REGEX_WARN_WORDS: OnceLock<Regex> = OnceLock::new();
+static REGEX_TIMESTAMP: OnceLock<Regex> = OnceLock::new();
+static REGEX_VARIABLE: OnceLock<Regex> = OnceLock::new();
+static REGEX_FIELD: OnceLock<Regex> = OnceLock::new();
 
-pub type LogBook = BTreeMap<String, Vec<LogEntry>>;
 pub type Entries = Vec<LogEntry>;
+// Entries are shared by the tabs showing them instead of copied into each one
+pub type LogBook = BTreeMap<String, Arc<Entries>>;
 
-#[derive(Clone, Debug, EnumIter, PartialEq)]
+#[derive(Clone, Debug, EnumIter, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "UPPERCASE")]
 pub enum LogLevel {
     Error,
     Warn,
@@ -38,18 +43,41 @@ pub enum LogLevel {
 }
 
 impl LogLevel {
+    // Accepts level names in any case, their single letter forms and syslog severities (0-7)
     fn from_str(s: &str) -> Self {
-        match s.trim() {
-            "ERROR" => LogLevel::Error,
-            "WARN" | "WARNING" => LogLevel::Warn,
-            "INFO" => LogLevel::Info,
-            "DEBUG" => LogLevel::Debug,
-            "TRACE" => LogLevel::Trace,
+        match s.trim().to_uppercase().as_str() {
+            "ERROR" | "ERR" | "E" | "FATAL" | "CRITICAL" | "CRIT" | "ALERT" | "EMERG" | "0"
+            | "1" | "2" | "3" => LogLevel::Error,
+            "WARN" | "WARNING" | "W" | "4" => LogLevel::Warn,
+            "INFO" | "I" | "NOTICE" | "5" | "6" => LogLevel::Info,
+            "DEBUG" | "D" | "7" => LogLevel::Debug,
+            "TRACE" | "T" | "VERBOSE" => LogLevel::Trace,
             _ => LogLevel::Unknown,
         }
     }
 }
 
+// Ordered by severity, Unknown being the least severe
+impl Ord for LogLevel {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let severity = |level: &LogLevel| match level {
+            LogLevel::Unknown => 0,
+            LogLevel::Trace => 1,
+            LogLevel::Debug => 2,
+            LogLevel::Info => 3,
+            LogLevel::Warn => 4,
+            LogLevel::Error => 5,
+        };
+        severity(self).cmp(&severity(other))
+    }
+}
+
+impl PartialOrd for LogLevel {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl std::fmt::Display for LogLevel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -63,60 +91,303 @@ impl std::fmt::Display for LogLevel {
     }
 }
 
-#[derive(Clone, Debug)]
+// User defined line format, a regex with named groups
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Profile {
+    pub name: String,
+    pub pattern: String,
+    pub enabled: bool,
+}
+
+impl Profile {
+    // Entries need at least a timestamp and a message, level and component are optional
+    pub fn regex(&self) -> Result<Regex, String> {
+        let regex = Regex::new(&self.pattern).map_err(|e| e.to_string())?;
+        for group in ["timestamp", "message"] {
+            if !regex.capture_names().flatten().any(|name| name == group) {
+                return Err(format!("Missing the {group} group"));
+            }
+        }
+        Ok(regex)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct ParseOptions {
+    // Compiled user profiles, see `Profile`
+    pub profiles: Vec<Regex>,
+    // Keep lines found before the first entry of a file as UNKNOWN entries instead of dropping them
+    pub keep_unparsed: bool,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
 pub struct LogEntry {
     pub timestamp: DateTime<Utc>,
     pub level: LogLevel,
     pub component: Option<String>,
     pub message: String,
+    /// Originating service, only set when entries from several services are combined
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<String>,
+    // Python traceback that followed the entry, ending with the exception line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub traceback: Option<String>,
+    // File of the bundle the entry was read from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_file: Option<String>,
+    // Note the user attached, only set on entries being exported
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    // Number of lines of the message and traceback, kept up to date while continuation lines are appended
+    #[serde(skip)]
+    pub lines: usize,
+    // Lines of the entry as they were in the file
+    #[serde(skip)]
+    pub raw: String,
 }
 
 impl LogEntry {
-    fn parse(line: &str) -> Option<Self> {
-        let (regex_general, regex_detailed) = unsafe {
-            INIT.call_once(|| {
-                REGEX_GENERAL = Some(Regex::new(
-                    concat!(
-                            r"^(?P<timestamp>\d{4}-\d{2}-\d{2}[T\s]\d{2}:\d{2}:\d{2}\.\d{3,6}Z?)\s*\|\s*",
-                            r"(?P<level>\S+)\s*\|\s*",
-                            // r"(?P<component>[\w-]+(?:[:]\w+)?[:]\w+[:]\d+)\s*-\s*",
-                            r"(?P<message>.+)$",
-                        )
-                    ).unwrap());
-
-                REGEX_DETAILED = Some(Regex::new(
-                    concat!(
-                            r"^(?P<timestamp>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\.\d{6}Z)\s+",
-                            r"(?P<level>\S+)\s+",
-                            // r"(?P<component>[^\s]+)\s+ThreadId\(\d+\)\s+",
-                            r"(?P<message>.+)$",
-                        )
-                    ).unwrap());
-            });
-            (
-                REGEX_GENERAL.as_ref().unwrap(),
-                REGEX_DETAILED.as_ref().unwrap(),
-            )
-        };
+    pub fn exception(&self) -> Option<&str> {
+        self.traceback.as_deref()?.lines().last()
+    }
+
+    pub fn full_message(&self) -> String {
+        match &self.traceback {
+            Some(traceback) => format!("{}\n{}", self.message, traceback),
+            None => self.message.clone(),
+        }
+    }
+
+    fn parse(line: &str, profiles: &[Regex]) -> Option<Self> {
+        if line.trim_start().starts_with('{') {
+            if let Some(entry) = Self::parse_json_line(line) {
+                return Some(entry);
+            }
+        }
+
+        let regex_general = REGEX_GENERAL.get_or_init(|| {
+            Regex::new(concat!(
+                r"^(?P<timestamp>\d{4}-\d{2}-\d{2}[T\s]\d{2}:\d{2}:\d{2}(?:\.\d{1,9})?(?:Z|[+-]\d{2}:?\d{2})?)\s*\|\s*",
+                r"(?P<level>\S+)\s*\|\s*",
+                r"(?:(?P<component>[\w.-]+(?::[\w./<>-]+)*:\d+)\s+-\s+)?",
+                r"(?P<message>.+)$",
+            ))
+            .unwrap()
+        });
+        let regex_detailed = REGEX_DETAILED.get_or_init(|| {
+            Regex::new(concat!(
+                r"^(?P<timestamp>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(?:\.\d{1,9})?(?:Z|[+-]\d{2}:?\d{2})?)\s+",
+                r"(?P<level>\S+)\s+",
+                r"(?:(?:(?P<component>\S+)\s+)?(?P<thread>ThreadId\(\d+\))\s+)?",
+                r"(?P<message>.+)$",
+            ))
+            .unwrap()
+        });
 
-        regex_general
-            .captures(line)
-            .or_else(|| regex_detailed.captures(line))
+        // User profiles come after the stock formats, the first matching one wins
+        [regex_general, regex_detailed]
+            .into_iter()
+            .chain(profiles)
+            .find_map(|regex| regex.captures(line))
             .and_then(|caps| {
                 let Ok(timestamp) = dateparser::parse(&caps["timestamp"]) else {
                     log::error!("Failed to parse timestamp");
                     return None;
                 };
-                let level = LogLevel::from_str(&caps["level"]);
+                // Profiles may leave the level out
+                let level = caps.name("level").map_or(LogLevel::Unknown, |level| {
+                    LogLevel::from_str(level.as_str())
+                });
+                let component = caps
+                    .name("component")
+                    .or_else(|| caps.name("thread"))
+                    .map(|component| component.as_str().to_string());
                 let message = caps["message"].to_string();
                 Some(LogEntry {
                     timestamp,
                     level,
-                    component: None,
+                    component,
                     message,
+                    service: None,
+                    traceback: None,
+                    source_file: None,
+                    note: None,
+                    lines: 1,
+                    raw: line.to_string(),
                 })
             })
     }
+
+    fn parse_json_line(line: &str) -> Option<Self> {
+        let serde_json::Value::Object(mut fields) = serde_json::from_str(line).ok()? else {
+            return None;
+        };
+
+        let timestamp = match fields.remove("timestamp")? {
+            serde_json::Value::String(timestamp) => dateparser::parse(&timestamp).ok()?,
+            _ => return None,
+        };
+        let level = match fields.remove("level") {
+            Some(serde_json::Value::String(level)) => LogLevel::from_str(&level),
+            Some(serde_json::Value::Number(level)) => LogLevel::from_str(&level.to_string()),
+            _ => LogLevel::Unknown,
+        };
+        let component = match fields.remove("target") {
+            Some(serde_json::Value::String(target)) => Some(target),
+            Some(target) => Some(target.to_string()),
+            None => None,
+        };
+        let mut message = match fields.remove("message") {
+            Some(serde_json::Value::String(message)) => message,
+            Some(message) => message.to_string(),
+            None => String::new(),
+        };
+
+        // Keep any extra structured data visible by folding it into the message
+        for (key, value) in fields {
+            let value = match value {
+                serde_json::Value::String(value) => value,
+                value => value.to_string(),
+            };
+            if !message.is_empty() {
+                message.push(' ');
+            }
+            message.push_str(&format!("{key}={value}"));
+        }
+
+        Some(LogEntry {
+            timestamp,
+            level,
+            component,
+            lines: message.lines().count().max(1),
+            message,
+            service: None,
+            traceback: None,
+            source_file: None,
+            note: None,
+            raw: line.to_string(),
+        })
+    }
+}
+
+// Stands for the variable parts of a message in its pattern
+pub const PATTERN_PLACEHOLDER: &str = "<*>";
+
+// Replaces numbers, hex values and ids in the first line of a message, so that lines logged by the
+// same statement share the same pattern
+pub fn message_pattern(message: &str) -> String {
+    let regex_variable = REGEX_VARIABLE.get_or_init(|| {
+        Regex::new(concat!(
+            r"(?i)\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b",
+            r"|\b0x[0-9a-f]+\b",
+            // Decimals, versions and addresses
+            r"|\d+(?:\.\d+)+",
+            r"|\b[0-9a-f]*\d[0-9a-f]*\b",
+            r"|\d+",
+        ))
+        .unwrap()
+    });
+    let line = message.lines().next().unwrap_or_default();
+    regex_variable
+        .replace_all(line, PATTERN_PLACEHOLDER)
+        .into_owned()
+}
+
+// `key=value` pairs of structured messages in the order they appear, quoted values without their
+// quotes
+pub fn message_fields(message: &str) -> Vec<(&str, &str)> {
+    let regex_field = REGEX_FIELD.get_or_init(|| {
+        Regex::new(r#"(?:^|\s)([A-Za-z_][\w.-]*)=(?:"((?:[^"\\]|\\.)*)"|(\S*))"#).unwrap()
+    });
+    regex_field
+        .captures_iter(message)
+        .filter_map(|captures| {
+            let value = captures.get(2).or_else(|| captures.get(3))?.as_str();
+            // Comparisons as in `a==b` are not fields
+            (!value.starts_with('=')).then_some((captures.get(1)?.as_str(), value))
+        })
+        .collect()
+}
+
+// Gives entries without a recognized level the one their message suggests
+pub fn guess_unknown_levels(entries: &mut [LogEntry]) {
+    let regex_error = REGEX_ERROR_WORDS.get_or_init(|| {
+        Regex::new(r"(?i)\b(?:panic(?:ked)?|fatal|critical|error|exception|failed)\b").unwrap()
+    });
+    let regex_warn = REGEX_WARN_WORDS
+        .get_or_init(|| Regex::new(r"(?i)\b(?:warn(?:ing)?|deprecated)\b").unwrap());
+
+    for entry in entries
+        .iter_mut()
+        .filter(|entry| entry.level == LogLevel::Unknown)
+    {
+        if regex_error.is_match(&entry.message) {
+            entry.level = LogLevel::Error;
+        } else if regex_warn.is_match(&entry.message) {
+            entry.level = LogLevel::Warn;
+        }
+    }
+}
+
+// Drops entries repeated with the same timestamp, level and message, like the overlap of rotated
+// logs. Expects the entries sorted by timestamp, repeats are looked for among equal timestamps.
+pub fn dedup_entries(entries: &mut Vec<LogEntry>) {
+    let mut kept: Vec<LogEntry> = Vec::with_capacity(entries.len());
+    let mut same_time = 0;
+    for entry in entries.drain(..) {
+        if kept
+            .get(same_time)
+            .is_some_and(|first| first.timestamp != entry.timestamp)
+        {
+            same_time = kept.len();
+        }
+        if kept[same_time..]
+            .iter()
+            .any(|other| other.level == entry.level && other.message == entry.message)
+        {
+            continue;
+        }
+        kept.push(entry);
+    }
+    *entries = kept;
+}
+
+#[derive(Clone, Debug)]
+pub struct Gap {
+    pub timestamp: DateTime<Utc>,
+    pub service: Option<String>,
+    // Silence before the entry, none when the entry looks like a service restart
+    pub duration: Option<chrono::TimeDelta>,
+}
+
+pub fn detect_gaps(entries: &[LogEntry], threshold: chrono::TimeDelta) -> Vec<Gap> {
+    let regex_restart =
+        REGEX_RESTART.get_or_init(|| Regex::new(r"(?i)^\s*(?:re)?start(?:ing|ed)\b").unwrap());
+
+    // Merged entries interleave services, so keep track of each one separately
+    let mut previous: BTreeMap<Option<&String>, DateTime<Utc>> = BTreeMap::new();
+    let mut gaps = vec![];
+    for entry in entries {
+        let service = entry.service.as_ref();
+        let Some(last) = previous.insert(service, entry.timestamp) else {
+            continue;
+        };
+        let duration = entry.timestamp - last;
+        if duration > threshold {
+            gaps.push(Gap {
+                timestamp: entry.timestamp,
+                service: service.cloned(),
+                duration: Some(duration),
+            });
+        } else if regex_restart.is_match(&entry.message) {
+            gaps.push(Gap {
+                timestamp: entry.timestamp,
+                service: service.cloned(),
+                duration: None,
+            });
+        }
+    }
+    gaps
 }
 
 #[derive(Clone)]
@@ -124,7 +395,54 @@ pub struct Info {
     pub service_name: String,
     pub percentage: f64,
     pub size: usize,
+    // Bytes to go through in total, the percentage is relative to it
+    pub total: usize,
     pub file: String,
+    pub started: DateTime<Utc>,
+}
+
+impl Info {
+    // Assumes the rest goes as fast as what was already done
+    pub fn remaining(&self) -> Option<chrono::TimeDelta> {
+        if self.percentage < 1.0 || self.percentage >= 100.0 {
+            return None;
+        }
+        let elapsed = (chrono::prelude::Utc::now() - self.started).num_milliseconds() as f64;
+        let remaining = elapsed * (100.0 - self.percentage) / self.percentage;
+        Some(chrono::TimeDelta::milliseconds(remaining as i64))
+    }
+}
+
+// Reports how far into the current file the parser is, so a single big file doesn't stall the progress
+struct ProgressReader<R> {
+    reader: R,
+    worker: Worker,
+    info: Info,
+    // Progress before this file and the weight of each byte read, both in units of `info.total`
+    processed: f64,
+    scale: f64,
+    read: u64,
+    reported: u64,
+}
+
+const PROGRESS_STEP: u64 = 1024 * 1024;
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.worker.is_cancelled() {
+            return Err(io::Error::other("cancelled"));
+        }
+        let read = self.reader.read(buf)?;
+        self.read += read as u64;
+        if self.read - self.reported >= PROGRESS_STEP && self.info.total > 0 {
+            self.reported = self.read;
+            let processed = self.processed + self.read as f64 * self.scale;
+            self.info.percentage = 100.0 * processed / self.info.total as f64;
+            self.worker
+                .set_state(ProcessingState::Processing(self.info.clone()));
+        }
+        Ok(read)
+    }
 }
 
 #[derive(Clone, Default)]
@@ -132,34 +450,77 @@ pub struct Processed {
     pub logbook: LogBook,
     pub size: usize,
     pub duration: chrono::TimeDelta,
+    // Services with logs in the bundle that were not selected to be loaded
+    pub skipped_services: BTreeSet<String>,
+    // Services whose files had no entries, left out of the logbook
+    pub empty_services: BTreeSet<String>,
+    pub stats: BTreeMap<String, LineStats>,
+    // Bytes of a single file read by the parser, following the file continues from there
+    pub consumed: u64,
+}
+
+// How the lines of a service were read
+#[derive(Clone, Debug, Default)]
+pub struct LineStats {
+    pub lines: usize,
+    // Lines starting an entry
+    pub parsed: usize,
+    // Lines appended to the entry before them, as multi-line messages and tracebacks
+    pub continuations: usize,
+    // Lines before the first entry of their file, dropped unless kept as UNKNOWN entries
+    pub unparsed: usize,
+}
+
+impl std::ops::AddAssign<&LineStats> for LineStats {
+    fn add_assign(&mut self, other: &LineStats) {
+        self.lines += other.lines;
+        self.parsed += other.parsed;
+        self.continuations += other.continuations;
+        self.unparsed += other.unparsed;
+    }
 }
 
 #[derive(Clone)]
 enum ProcessingState {
     Done(Processed),
+    Discovered(BTreeSet<String>),
     Processing(Info),
+    Error(String),
     None,
 }
 
 #[derive(Clone)]
 pub struct Worker {
     state: Arc<Mutex<ProcessingState>>,
+    cancelled: Arc<AtomicBool>,
 }
 
 impl Default for Worker {
     fn default() -> Self {
         Worker {
             state: Arc::new(Mutex::new(ProcessingState::None)),
+            cancelled: Arc::new(AtomicBool::new(false)),
         }
     }
 }
 
 impl Worker {
-    pub fn logs(&self) -> Option<LogBook> {
-        if let ProcessingState::Done(p) = &*self.state.lock().unwrap() {
-            return Some(p.logbook.clone());
+    // Stops the processing task at its next check and drops whatever it had processed so far
+    pub fn cancel(&self) {
+        let mut state = self.state.lock().unwrap();
+        self.cancelled.store(true, Ordering::Relaxed);
+        *state = ProcessingState::None;
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn set_state(&self, state: ProcessingState) {
+        let mut current = self.state.lock().unwrap();
+        if !self.is_cancelled() {
+            *current = state;
         }
-        None
     }
 
     pub fn processed(&self) -> Option<Processed> {
@@ -169,180 +530,1217 @@ impl Worker {
         None
     }
 
+    pub fn discovered(&self) -> Option<BTreeSet<String>> {
+        if let ProcessingState::Discovered(services) = &*self.state.lock().unwrap() {
+            return Some(services.clone());
+        }
+        None
+    }
+
     pub fn info(&self) -> Option<Info> {
         if let ProcessingState::Processing(info) = &*self.state.lock().unwrap() {
             return Some(info.clone());
         }
         None
     }
-}
 
-impl Worker {
-    fn process(&self, file: String) {
-        let mut state = self.state.lock().unwrap();
-        if let ProcessingState::None = *state {
-            *state = ProcessingState::Processing(Info {
-                service_name: file.split('/').next().unwrap().into(),
-                percentage: 0.0,
-                size: 0,
-                file,
-            });
+    pub fn error(&self) -> Option<String> {
+        if let ProcessingState::Error(error) = &*self.state.lock().unwrap() {
+            return Some(error.clone());
         }
+        None
+    }
+
+    pub fn failed(error: String) -> Self {
+        let worker = Worker::default();
+        worker.set_state(ProcessingState::Error(error));
+        worker
     }
 }
 
 fn get_service_name(file: &str) -> String {
     let names = file.split('/').collect::<Vec<&str>>();
-    let service_name = if names.len() > 1 {
+    if names.len() > 1 {
         names[names.len() - 2].to_string()
     } else {
         names[0].to_string()
+    }
+}
+
+fn get_service_name_from_file_name(file_name: &str) -> String {
+    let base_name = file_name.rsplit(['/', '\\']).next().unwrap_or(file_name);
+    base_name
+        .split('.')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(base_name)
+        .to_string()
+}
+
+fn is_log(file_name: &str) -> bool {
+    file_name.ends_with(".log") || is_compressed(file_name) || rotation(file_name) > 0
+}
+
+// Compressed files can't be read again from where they were left
+pub fn is_compressed(file_name: &str) -> bool {
+    Compression::detect(file_name, &[]).is_some()
+}
+
+// Extensions of the log files that can be opened on their own or found in bundles
+pub const LOG_EXTENSIONS: &[&str] = &["log", "gz", "bz2", "xz", "zip"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Compression {
+    Gzip,
+    Bzip2,
+    Xz,
+}
+
+impl Compression {
+    const ALL: [Compression; 3] = [Compression::Gzip, Compression::Bzip2, Compression::Xz];
+
+    fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => ".gz",
+            Compression::Bzip2 => ".bz2",
+            Compression::Xz => ".xz",
+        }
+    }
+
+    fn magic(self) -> &'static [u8] {
+        match self {
+            Compression::Gzip => &[0x1f, 0x8b],
+            Compression::Bzip2 => b"BZh",
+            // Only the first 4 bytes of a file are looked at
+            Compression::Xz => &[0xfd, b'7', b'z', b'X'],
+        }
+    }
+
+    // From the extension of the file, or its first bytes when they are known
+    fn detect(file_name: &str, magic: &[u8]) -> Option<Self> {
+        Self::ALL.into_iter().find(|compression| {
+            file_name.ends_with(compression.extension())
+                || (!magic.is_empty() && magic.starts_with(compression.magic()))
+        })
+    }
+
+    fn strip(file_name: &str) -> &str {
+        Self::ALL
+            .into_iter()
+            .find_map(|compression| file_name.strip_suffix(compression.extension()))
+            .unwrap_or(file_name)
+    }
+}
+
+// Decompresses the file as it is read, plain files are read as they are
+fn decompress<'a, R: Read + 'a>(
+    reader: R,
+    compression: Option<Compression>,
+) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(match compression {
+        None => Box::new(reader),
+        Some(Compression::Gzip) => Box::new(GzDecoder::new(reader)),
+        Some(Compression::Bzip2) => Box::new(bzip2_rs::DecoderReader::new(reader)),
+        // lzma-rs only decompresses whole streams
+        Some(Compression::Xz) => {
+            let mut decompressed = LimitedWriter {
+                data: vec![],
+                remaining: MAX_DECOMPRESSED_SIZE,
+            };
+            lzma_rs::xz_decompress(&mut BufReader::new(reader), &mut decompressed)
+                .map_err(io::Error::other)?;
+            Box::new(io::Cursor::new(decompressed.data))
+        }
+    })
+}
+
+struct LimitedWriter {
+    data: Vec<u8>,
+    remaining: u64,
+}
+
+impl Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() as u64 > self.remaining {
+            return Err(io::Error::other(format!(
+                "File exceeds {} of decompressed data",
+                bytesize::ByteSize(MAX_DECOMPRESSED_SIZE)
+            )));
+        }
+        self.remaining -= buf.len() as u64;
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Rotation number of a log file, 2 for service.log.2.gz and 0 for the current service.log
+fn rotation(file_name: &str) -> u32 {
+    let name = Compression::strip(file_name);
+    name.rsplit_once('.')
+        .filter(|(base, _)| base.ends_with(".log"))
+        .and_then(|(_, number)| number.parse().ok())
+        .unwrap_or(0)
+}
+
+// Processing order of the archive files, so the files of a service are appended oldest first:
+// service.log.2.gz, service.log.1.xz, service.log
+fn processing_order<R: Read + Seek>(archive: &ZipArchive<R>) -> Vec<usize> {
+    let mut order: Vec<(usize, &str)> = (0..archive.len())
+        .filter_map(|index| Some((index, archive.name_for_index(index)?)))
+        .collect();
+    order.sort_by_key(|(_, name)| {
+        let directory = name.rsplit_once('/').map_or("", |(directory, _)| directory);
+        (directory, std::cmp::Reverse(rotation(name)), *name)
+    });
+    order.into_iter().map(|(index, _)| index).collect()
+}
+
+// Lists the services of the bundle in the background, nested zips have to be inflated for it
+pub fn discover_services(source: LogSource, file_name: String) -> Worker {
+    let worker = Worker::default();
+    let cloned_worker = worker.clone();
+
+    tokio::spawn(async move {
+        cloned_worker.set_state(ProcessingState::Processing(Info {
+            service_name: "Listing services".into(),
+            percentage: 0.0,
+            size: 0,
+            total: 0,
+            file: file_name,
+            started: chrono::prelude::Utc::now(),
+        }));
+        match archive_services(&source) {
+            Ok(services) => cloned_worker.set_state(ProcessingState::Discovered(services)),
+            Err(error) => {
+                log::error!("{error}");
+                cloned_worker.set_state(ProcessingState::Error(error));
+            }
+        }
+    });
+
+    worker
+}
+
+// Services with log files in the bundle, including the ones inside nested zips
+fn archive_services(source: &LogSource) -> Result<BTreeSet<String>, String> {
+    let mut services = BTreeSet::new();
+    let opened = match source {
+        LogSource::Data(data) => ZipArchive::new(io::Cursor::new(data))
+            .map(|archive| discover_archive_services(archive, 0, &mut services)),
+        #[cfg(not(target_arch = "wasm32"))]
+        LogSource::Path(path) => {
+            let file = std::fs::File::open(path)
+                .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+            ZipArchive::new(BufReader::new(file))
+                .map(|archive| discover_archive_services(archive, 0, &mut services))
+        }
     };
-    service_name
+    opened.map_err(|e| format!("Failed to open zip file: {e}"))?;
+    Ok(services)
 }
 
-pub fn process_from_zip(data: Vec<u8>, allowed_versions: Vec<String>) -> Worker {
+fn discover_archive_services<R: Read + io::Seek>(
+    mut archive: ZipArchive<R>,
+    depth: usize,
+    services: &mut BTreeSet<String>,
+) {
+    for i in 0..archive.len() {
+        let Ok(mut file) = archive.by_index(i) else {
+            continue;
+        };
+        let file_name = file.name().to_string();
+        if is_log(&file_name) {
+            services.insert(get_service_name(&file_name));
+        } else if file_name.ends_with(".zip") && depth + 1 < MAX_ARCHIVE_DEPTH {
+            let mut inner_data = Vec::new();
+            if (&mut file)
+                .take(MAX_DECOMPRESSED_SIZE)
+                .read_to_end(&mut inner_data)
+                .is_err()
+            {
+                continue;
+            }
+            match ZipArchive::new(io::Cursor::new(inner_data)) {
+                Ok(inner) => discover_archive_services(inner, depth + 1, services),
+                Err(e) => log::error!("Failed to open inner zip: {} {:#?}", &file_name, e),
+            }
+        }
+    }
+}
+
+pub fn merge_services<'a>(
+    logbook: &LogBook,
+    services: impl IntoIterator<Item = &'a String>,
+) -> Entries {
+    let sources: Vec<(&String, &Arc<Entries>)> = services
+        .into_iter()
+        .filter_map(|service| logbook.get(service).map(|entries| (service, entries)))
+        .collect();
+
+    // Every service is already sorted by timestamp, so a k-way merge keeps the result sorted
+    let mut heap: BinaryHeap<Reverse<(DateTime<Utc>, usize, usize)>> = sources
+        .iter()
+        .enumerate()
+        .filter_map(|(source, (_, entries))| {
+            entries
+                .first()
+                .map(|entry| Reverse((entry.timestamp, source, 0)))
+        })
+        .collect();
+
+    let mut merged = Vec::with_capacity(sources.iter().map(|(_, entries)| entries.len()).sum());
+    while let Some(Reverse((_, source, index))) = heap.pop() {
+        let (service, entries) = sources[source];
+        merged.push(LogEntry {
+            service: Some(service.clone()),
+            ..entries[index].clone()
+        });
+        if let Some(next) = entries.get(index + 1) {
+            heap.push(Reverse((next.timestamp, source, index + 1)));
+        }
+    }
+    merged
+}
+
+// Errors (and optionally warnings) of every service, with consecutive repeats folded into one entry
+pub fn summarize_errors(
+    logbook: &LogBook,
+    include_warnings: bool,
+) -> (Entries, BTreeMap<String, usize>) {
+    let mut counts = BTreeMap::new();
+    let mut summary: LogBook = BTreeMap::new();
+    for (service, entries) in logbook {
+        let mut collapsed: Vec<(LogEntry, usize)> = vec![];
+        for entry in entries.iter().filter(|entry| {
+            entry.level == LogLevel::Error || (include_warnings && entry.level == LogLevel::Warn)
+        }) {
+            match collapsed.last_mut() {
+                Some((last, repeats))
+                    if last.level == entry.level && last.message == entry.message =>
+                {
+                    *repeats += 1
+                }
+                _ => collapsed.push((entry.clone(), 1)),
+            }
+        }
+        if collapsed.is_empty() {
+            continue;
+        }
+
+        counts.insert(
+            service.clone(),
+            collapsed.iter().map(|(_, repeats)| repeats).sum(),
+        );
+        let entries = collapsed
+            .into_iter()
+            .map(|(mut entry, repeats)| {
+                if repeats > 1 {
+                    entry.message += &format!(" (x{repeats})");
+                }
+                entry
+            })
+            .collect();
+        summary.insert(service.clone(), Arc::new(entries));
+    }
+
+    (merge_services(&summary, summary.keys()), counts)
+}
+
+// Where a log file or bundle comes from, native builds stream files from disk instead of
+// loading them into memory first
+#[derive(Clone)]
+pub enum LogSource {
+    Data(Arc<[u8]>),
+    #[cfg(not(target_arch = "wasm32"))]
+    Path(std::path::PathBuf),
+}
+
+impl LogSource {
+    // Modification time of a file on disk, to notice when it changed after being loaded
+    pub fn modified(&self) -> Option<std::time::SystemTime> {
+        match self {
+            LogSource::Data(_) => None,
+            #[cfg(not(target_arch = "wasm32"))]
+            LogSource::Path(path) => std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+        }
+    }
+}
+
+trait ReadSeek: Read + io::Seek + Send {}
+
+impl<T: Read + io::Seek + Send> ReadSeek for T {}
+
+impl LogSource {
+    fn open(self) -> Result<(Box<dyn ReadSeek>, u64), String> {
+        match self {
+            LogSource::Data(data) => {
+                let size = data.len() as u64;
+                Ok((Box::new(io::Cursor::new(data)), size))
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            LogSource::Path(path) => {
+                let open = || {
+                    let file = std::fs::File::open(&path)?;
+                    let size = file.metadata()?.len();
+                    Ok::<_, io::Error>((Box::new(BufReader::new(file)) as Box<dyn ReadSeek>, size))
+                };
+                open().map_err(|e| format!("Failed to read {}: {e}", path.display()))
+            }
+        }
+    }
+}
+
+pub fn process_from_file(
+    source: LogSource,
+    file_name: String,
+    allowed_versions: Vec<String>,
+    options: ParseOptions,
+) -> Worker {
     let worker = Worker::default();
     let cloned_worker = worker.clone();
 
     tokio::spawn(async move {
         let started = chrono::prelude::Utc::now();
-        let reader = std::io::Cursor::new(data);
-        let mut archive = ZipArchive::new(reader).unwrap();
+        let service_name = get_service_name_from_file_name(&file_name);
+        let info = Info {
+            service_name: service_name.clone(),
+            percentage: 0.0,
+            size: 0,
+            total: 0,
+            file: file_name.clone(),
+            started,
+        };
+        cloned_worker.set_state(ProcessingState::Processing(info.clone()));
+
+        let (mut reader, total) = match source.open() {
+            Ok(opened) => opened,
+            Err(error) => {
+                log::error!("{error}");
+                cloned_worker.set_state(ProcessingState::Error(error));
+                return;
+            }
+        };
+        let mut magic = [0; 4];
+        let magic_size = reader.read(&mut magic).unwrap_or(0);
+        let magic = &magic[..magic_size];
+        if let Err(e) = reader.rewind() {
+            cloned_worker.set_state(ProcessingState::Error(format!(
+                "Failed to read {file_name}: {e}"
+            )));
+            return;
+        }
+
+        if file_name.ends_with(".zip") || magic.starts_with(b"PK\x03\x04") {
+            process_zip(cloned_worker, reader, allowed_versions, options).await;
+            return;
+        }
+
+        let compression = Compression::detect(&file_name, magic);
+        let mut reader = ProgressReader {
+            reader,
+            worker: cloned_worker.clone(),
+            info: Info {
+                total: total as usize,
+                ..info
+            },
+            processed: 0.0,
+            scale: 1.0,
+            read: 0,
+            reported: 0,
+        };
+        let processed = decompress(&mut reader, compression)
+            .and_then(|reader| process_log_file(std::io::BufReader::new(reader), &options));
+        // Lines appended while the file was parsed come after what was read
+        let consumed = reader.read;
+
+        let (mut entries, file_size, stats) = match processed {
+            Ok(processed) => processed,
+            Err(e) => {
+                log::error!("Failed to process {}: {:#?}", &file_name, e);
+                cloned_worker.set_state(ProcessingState::Error(format!(
+                    "Failed to process {file_name}: {e}"
+                )));
+                return;
+            }
+        };
+        if entries.is_empty() {
+            cloned_worker.set_state(ProcessingState::Error(format!(
+                "No log entries found in {file_name}"
+            )));
+            return;
+        }
+        entries.sort_by_key(|entry| entry.timestamp);
+
         let mut logs: LogBook = BTreeMap::new();
-        log::info!("Started processing {:#?}", chrono::prelude::Utc::now());
-        let size = archive.len();
-        let mut file_size = 0;
-        for i in 0..size {
-            let mut file = archive.by_index(i).unwrap();
-            if !file.is_file() {
-                continue;
+        logs.insert(service_name.clone(), Arc::new(entries));
+        cloned_worker.set_state(ProcessingState::Done(Processed {
+            logbook: logs,
+            size: file_size,
+            duration: chrono::prelude::Utc::now() - started,
+            skipped_services: Default::default(),
+            empty_services: Default::default(),
+            stats: BTreeMap::from([(service_name, stats)]),
+            consumed,
+        }));
+    });
+
+    worker
+}
+
+pub fn process_from_zip(
+    source: LogSource,
+    allowed_versions: Vec<String>,
+    options: ParseOptions,
+) -> Worker {
+    let worker = Worker::default();
+    let cloned_worker = worker.clone();
+
+    tokio::spawn(async move {
+        match source.open() {
+            Ok((reader, _)) => process_zip(cloned_worker, reader, allowed_versions, options).await,
+            Err(error) => {
+                log::error!("{error}");
+                cloned_worker.set_state(ProcessingState::Error(error));
             }
-            let file_name = file.name().to_string();
-            let service_name = get_service_name(&file_name);
-            if !allowed_versions.contains(&service_name) {
-                continue;
+        }
+    });
+
+    worker
+}
+
+pub fn process_from_url(
+    url: String,
+    allowed_versions: Vec<String>,
+    options: ParseOptions,
+) -> Worker {
+    let worker = Worker::default();
+    let cloned_worker = worker.clone();
+
+    let future = async move {
+        let data = match download(&cloned_worker, &url).await {
+            Ok(data) => data,
+            Err(e) => {
+                log::error!("Failed to download {}: {}", &url, e);
+                cloned_worker.set_state(ProcessingState::Error(format!(
+                    "Failed to download {url}: {e}"
+                )));
+                return;
+            }
+        };
+        if cloned_worker.is_cancelled() {
+            return;
+        }
+        let reader = Box::new(io::Cursor::new(data));
+        process_zip(cloned_worker, reader, allowed_versions, options).await;
+    };
+
+    // reqwest futures are not Send on the web
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(future);
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::spawn(future);
+
+    worker
+}
+
+async fn download(worker: &Worker, url: &str) -> Result<Vec<u8>, String> {
+    let started = chrono::prelude::Utc::now();
+    let mut response = reqwest::get(url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?;
+    // The header is only trusted for the progress, the data grows as it arrives
+    let total = response.content_length();
+    let mut data = vec![];
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        if worker.is_cancelled() {
+            break;
+        }
+        if (data.len() + chunk.len()) as u64 > MAX_DECOMPRESSED_SIZE {
+            return Err(format!(
+                "File exceeds {}",
+                bytesize::ByteSize(MAX_DECOMPRESSED_SIZE)
+            ));
+        }
+        data.extend_from_slice(&chunk);
+        worker.set_state(ProcessingState::Processing(Info {
+            service_name: "Downloading".into(),
+            percentage: total.map_or(0.0, |total| 100.0 * data.len() as f64 / total as f64),
+            size: data.len(),
+            total: total.unwrap_or_default() as usize,
+            file: url.to_string(),
+            started,
+        }));
+    }
+    Ok(data)
+}
+
+async fn process_zip(
+    worker: Worker,
+    reader: Box<dyn ReadSeek>,
+    allowed_versions: Vec<String>,
+    options: ParseOptions,
+) {
+    let started = chrono::prelude::Utc::now();
+    log::info!("Started processing {:#?}", chrono::prelude::Utc::now());
+    let mut context = ArchiveContext {
+        worker: worker.clone(),
+        allowed_versions,
+        options,
+        logs: BTreeMap::new(),
+        skipped_services: BTreeSet::new(),
+        stats: BTreeMap::new(),
+        file_size: 0,
+        decompressed_size: 0,
+        total: 0,
+        processed: 0.0,
+        started,
+    };
+    if let Err(error) = process_archive(&mut context, reader, 0, None).await {
+        log::error!("{error}");
+        worker.set_state(ProcessingState::Error(error));
+        return;
+    }
+    log::info!("Done with processing {:#?}", chrono::prelude::Utc::now());
+
+    let (logs, empty): (BTreeMap<String, Entries>, _) = context
+        .logs
+        .into_iter()
+        .partition(|(_, entries)| !entries.is_empty());
+    let logs = logs
+        .into_iter()
+        .map(|(service, mut entries)| {
+            entries.sort_by_key(|entry| entry.timestamp);
+            (service, Arc::new(entries))
+        })
+        .collect();
+    worker.set_state(ProcessingState::Done(Processed {
+        logbook: logs,
+        size: context.file_size,
+        duration: chrono::prelude::Utc::now() - started,
+        skipped_services: context.skipped_services,
+        empty_services: empty.into_keys().collect(),
+        stats: context.stats,
+        consumed: 0,
+    }));
+}
+
+// Bundles produced by log-zipper can contain zips inside zips
+const MAX_ARCHIVE_DEPTH: usize = 4;
+// Everything ends up in memory, so refuse to inflate archives beyond this
+const MAX_DECOMPRESSED_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
+#[cfg(target_arch = "wasm32")]
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+#[cfg(not(target_arch = "wasm32"))]
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+struct ArchiveContext {
+    worker: Worker,
+    allowed_versions: Vec<String>,
+    options: ParseOptions,
+    logs: BTreeMap<String, Entries>,
+    skipped_services: BTreeSet<String>,
+    stats: BTreeMap<String, LineStats>,
+    file_size: usize,
+    decompressed_size: u64,
+    // Size of the eligible files of the bundle and how much of it was processed, nested
+    // archives share the size of their zip file among their own files
+    total: u64,
+    processed: f64,
+    started: DateTime<Utc>,
+}
+
+impl ArchiveContext {
+    fn is_eligible(&self, file_name: &str) -> bool {
+        file_name.ends_with(".zip")
+            || (is_log(file_name) && self.allowed_versions.contains(&get_service_name(file_name)))
+    }
+
+    fn eligible_size<R: Read + io::Seek>(&self, archive: &mut ZipArchive<R>) -> u64 {
+        (0..archive.len())
+            .filter_map(|i| {
+                let file = archive.by_index_raw(i).ok()?;
+                (file.is_file() && self.is_eligible(file.name())).then(|| file.size())
+            })
+            .sum()
+    }
+
+    fn info(&self, file_name: &str) -> Info {
+        Info {
+            service_name: get_service_name(file_name),
+            percentage: if self.total > 0 {
+                100.0 * self.processed / self.total as f64
+            } else {
+                0.0
+            },
+            size: self.file_size,
+            total: self.total as usize,
+            file: file_name.to_string(),
+            started: self.started,
+        }
+    }
+
+    fn read_limited<R: Read, T>(
+        &mut self,
+        reader: R,
+        read: impl FnOnce(&mut SizeLimit<R>) -> T,
+    ) -> Result<T, String> {
+        let remaining = MAX_DECOMPRESSED_SIZE.saturating_sub(self.decompressed_size);
+        let mut limited = SizeLimit {
+            reader,
+            remaining,
+            exceeded: false,
+        };
+        let result = read(&mut limited);
+        self.decompressed_size += remaining - limited.remaining;
+        if limited.exceeded {
+            return Err(format!(
+                "Bundle exceeds {} of decompressed data",
+                bytesize::ByteSize(MAX_DECOMPRESSED_SIZE)
+            ));
+        }
+        Ok(result)
+    }
+}
+
+struct SizeLimit<R> {
+    reader: R,
+    remaining: u64,
+    exceeded: bool,
+}
+
+impl<R: Read> Read for SizeLimit<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let max = (self.remaining + 1).min(buf.len() as u64) as usize;
+        let read = self.reader.read(&mut buf[..max])?;
+        if read as u64 > self.remaining {
+            self.exceeded = true;
+            return Err(io::Error::other("size limit exceeded"));
+        }
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+fn process_archive(
+    context: &mut ArchiveContext,
+    reader: Box<dyn ReadSeek>,
+    depth: usize,
+    weight: Option<f64>,
+) -> BoxFuture<'_, Result<(), String>> {
+    Box::pin(async move {
+        let mut archive = match ZipArchive::new(reader) {
+            Ok(archive) => archive,
+            Err(e) if depth == 0 => return Err(format!("Failed to open zip file: {e}")),
+            Err(e) => {
+                log::error!("Failed to open inner zip: {:#?}", e);
+                return Ok(());
+            }
+        };
+
+        // The top level archive defines the total, nested ones spread their weight over their files
+        let eligible_size = context.eligible_size(&mut archive);
+        let scale = match weight {
+            Some(weight) => weight / eligible_size.max(1) as f64,
+            None => {
+                context.total = eligible_size;
+                1.0
+            }
+        };
+
+        for i in processing_order(&archive) {
+            if context.worker.is_cancelled() {
+                return Ok(());
             }
+            let (file_name, inner_data) = {
+                let Ok(mut file) = archive.by_index(i) else {
+                    continue;
+                };
+                if !file.is_file() || file.size() == 0 {
+                    continue;
+                }
+                let file_name = file.name().to_string();
 
-            if file.size() > 0 {
-                let processed = if file.name().ends_with(".gz") {
-                    process_log_file(std::io::BufReader::new(GzDecoder::new(&mut file)))
-                } else if file.name().ends_with(".log") {
-                    process_log_file(std::io::BufReader::new(&mut file))
-                } else if file.name().ends_with(".zip") {
+                if file_name.ends_with(".zip") {
+                    if depth + 1 >= MAX_ARCHIVE_DEPTH {
+                        log::warn!("Skipping {file_name}, archives are nested too deep");
+                        continue;
+                    }
                     let mut inner_data = Vec::new();
-                    file.read_to_end(&mut inner_data).unwrap();
-                    // log::info!("Content: {:?}", &inner_data[0..10]);
-                    let mut archive = match ZipArchive::new(std::io::Cursor::new(inner_data)) {
-                        Ok(archive) => archive,
-                        Err(e) => {
-                            log::error!("Failed to open inner zip: {} {:#?}", &file_name, e);
-                            continue;
-                        }
+                    let read = context
+                        .read_limited(&mut file, |reader| reader.read_to_end(&mut inner_data))?;
+                    if let Err(e) = read {
+                        log::error!("Failed to read inner zip: {} {:#?}", &file_name, e);
+                        continue;
+                    }
+                    (file_name, Some((inner_data, file.size() as f64 * scale)))
+                } else if is_log(&file_name) {
+                    let service_name = get_service_name(&file_name);
+                    if !context.allowed_versions.contains(&service_name) {
+                        context.skipped_services.insert(service_name);
+                        continue;
+                    }
+
+                    let file_weight = file.size() as f64 * scale;
+                    let reader = ProgressReader {
+                        reader: &mut file,
+                        worker: context.worker.clone(),
+                        info: context.info(&file_name),
+                        processed: context.processed,
+                        scale,
+                        read: 0,
+                        reported: 0,
                     };
-                    let size_u = archive.len();
-                    for u in 0..size_u {
-                        let mut file = archive.by_index(u).unwrap();
-                        let file_name = file.name().to_string();
-                        let service_name = get_service_name(&file_name);
-                        if !allowed_versions.contains(&service_name) {
-                            continue;
-                        }
-                        // log::info!("Processing zip: {}", file_name);
-                        if !file.is_file() {
-                            continue;
-                        }
-                        if file.size() > 0 {
-                            let processed = if file.name().ends_with(".gz") {
-                                process_log_file(std::io::BufReader::new(GzDecoder::new(&mut file)))
-                            } else if file.name().ends_with(".log") {
-                                process_log_file(std::io::BufReader::new(&mut file))
-                            } else {
-                                continue;
-                            };
-                            let (mut entries, processed_size) = processed.unwrap();
-                            file_size += processed_size;
-                            if let Some(service) = logs.get_mut(&service_name) {
-                                service.append(&mut entries);
-                            } else {
-                                logs.insert(service_name.clone(), entries);
-                            }
-                        } else {
-                            continue;
-                        };
-
-                        *cloned_worker.state.lock().unwrap() = ProcessingState::Processing(Info {
-                            service_name,
-                            percentage: 100.0 * (i as f64 + u as f64 / size_u as f64) / size as f64,
-                            size: file_size,
-                            file: file.name().to_string(),
-                        });
-
-                        // Allow frontend to render
-                        #[cfg(target_arch = "wasm32")]
-                        tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+                    let options = std::mem::take(&mut context.options);
+                    let processed = match decompress(reader, Compression::detect(&file_name, &[])) {
+                        Ok(reader) => context.read_limited(reader, |reader| {
+                            process_log_file(BufReader::new(reader), &options)
+                        }),
+                        Err(e) => Ok(Err(e)),
+                    };
+                    context.options = options;
+                    let processed = processed?;
+                    context.processed += file_weight;
+                    let (mut entries, processed_size, stats) =
+                        processed.map_err(|e| format!("Failed to process {file_name}: {e}"))?;
+                    context.file_size += processed_size;
+                    *context.stats.entry(service_name.clone()).or_default() += &stats;
+                    for entry in &mut entries {
+                        entry.source_file = Some(file_name.clone());
                     }
-                    continue;
+                    context
+                        .logs
+                        .entry(service_name)
+                        .or_default()
+                        .append(&mut entries);
+                    (file_name, None)
                 } else {
                     continue;
-                };
-
-                let (mut entries, processed_size) = processed.unwrap();
-                file_size += processed_size;
-                if let Some(service) = logs.get_mut(&service_name) {
-                    service.append(&mut entries);
-                } else {
-                    logs.insert(service_name.clone(), entries);
                 }
+            };
+
+            if let Some((inner_data, weight)) = inner_data {
+                let processed = context.processed;
+                let reader = Box::new(io::Cursor::new(inner_data));
+                process_archive(context, reader, depth + 1, Some(weight)).await?;
+                // Keep the progress consistent even if the inner archive had nothing to process
+                context.processed = processed + weight;
+                continue;
             }
 
-            *cloned_worker.state.lock().unwrap() = ProcessingState::Processing(Info {
-                service_name,
-                percentage: 100.0 * i as f64 / size as f64,
-                size: file_size,
-                file: file.name().to_string(),
-            });
+            context
+                .worker
+                .set_state(ProcessingState::Processing(context.info(&file_name)));
 
             // Allow frontend to render
             #[cfg(target_arch = "wasm32")]
             tokio::time::sleep(std::time::Duration::from_millis(1)).await;
         }
-        log::info!("Done with processing {:#?}", chrono::prelude::Utc::now());
+        Ok(())
+    })
+}
 
-        let keys: Vec<String> = logs.keys().cloned().collect();
-        for key in keys {
-            logs.get_mut(&key)
-                .unwrap()
-                .sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-        }
-        *cloned_worker.state.lock().unwrap() = ProcessingState::Done(Processed {
-            logbook: logs,
-            size: file_size,
-            duration: chrono::prelude::Utc::now() - started,
-        });
+const TRACEBACK_HEADER: &str = "Traceback (most recent call last):";
+
+// Lines between the exceptions of a chained traceback
+fn continues_traceback(line: &str) -> bool {
+    line.is_empty()
+        || line.starts_with("During handling of the above exception")
+        || line.starts_with("The above exception was the direct cause")
+}
+
+// Best effort timestamp of a line no format matched
+fn find_timestamp(line: &str) -> Option<DateTime<Utc>> {
+    let regex_timestamp = REGEX_TIMESTAMP.get_or_init(|| {
+        Regex::new(r"\d{4}-\d{2}-\d{2}[T\s]\d{2}:\d{2}:\d{2}(?:\.\d{1,9})?").unwrap()
     });
+    dateparser::parse(regex_timestamp.find(line)?.as_str()).ok()
+}
 
-    worker
+fn unparsed_entry(line: &str, timestamp: DateTime<Utc>) -> LogEntry {
+    LogEntry {
+        timestamp,
+        level: LogLevel::Unknown,
+        component: None,
+        message: line.to_string(),
+        service: None,
+        traceback: None,
+        source_file: None,
+        note: None,
+        lines: 1,
+        raw: line.to_string(),
+    }
+}
+
+// Entries of the complete lines appended to a file since `offset`, with the offset after them.
+// A file shorter than the offset was truncated or rotated, so it is read again from its start.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_appended(
+    path: &std::path::Path,
+    offset: u64,
+    options: &ParseOptions,
+) -> io::Result<(Vec<LogEntry>, u64)> {
+    let mut file = std::fs::File::open(path)?;
+    let offset = if file.metadata()?.len() < offset {
+        0
+    } else {
+        offset
+    };
+    file.seek(io::SeekFrom::Start(offset))?;
+    let mut data = vec![];
+    file.read_to_end(&mut data)?;
+    // A line still being written is read once it is complete
+    let complete = data
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |end| end + 1);
+    data.truncate(complete);
+    let (entries, _, _) = process_log_file(BufReader::new(io::Cursor::new(data)), options)?;
+    Ok((entries, offset + complete as u64))
 }
 
-pub fn process_log_file<R: Read>(reader: BufReader<R>) -> io::Result<(Vec<LogEntry>, usize)> {
-    let lines: Vec<String> = reader.lines().map_while(Result::ok).collect();
+pub fn process_log_file<R: Read>(
+    mut reader: BufReader<R>,
+    options: &ParseOptions,
+) -> io::Result<(Vec<LogEntry>, usize, LineStats)> {
     let mut size = 0;
     let mut entries = vec![];
-    for line in lines {
+    let mut in_traceback = false;
+    let mut stats = LineStats::default();
+    // Kept lines without a timestamp of their own take the one of the first entry
+    let mut pending: Vec<(Option<DateTime<Utc>>, String)> = vec![];
+    let mut buffer = vec![];
+    loop {
+        buffer.clear();
+        match reader.read_until(b'\n', &mut buffer) {
+            Ok(0) => break,
+            Ok(_) => {}
+            // As with a truncated archive, what was read so far is kept
+            Err(e) => {
+                log::warn!("Stopped reading a log file: {e}");
+                break;
+            }
+        }
+        // Invalid bytes, as in some hardware messages, become replacement characters instead of
+        // losing the line
+        let line = String::from_utf8_lossy(&buffer);
+        let line = line.strip_suffix('\n').unwrap_or(&line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
         size += line.len();
+        stats.lines += 1;
         let line = line.trim_end_matches(['\0']);
-        if let Some(entry) = LogEntry::parse(&line) {
+        if let Some(entry) = LogEntry::parse(line, &options.profiles) {
+            stats.parsed += 1;
+            for (timestamp, line) in pending.drain(..) {
+                entries.push(unparsed_entry(&line, timestamp.unwrap_or(entry.timestamp)));
+            }
             entries.push(entry);
+            in_traceback = false;
             continue;
         }
 
         let Some(last_entry) = entries.last_mut() else {
+            // Until the first entry there is nothing to attach the line to
+            if !line.trim().is_empty() {
+                stats.unparsed += 1;
+                if options.keep_unparsed {
+                    pending.push((find_timestamp(line), line.to_string()));
+                }
+            }
             continue;
         };
+        last_entry.raw.push('\n');
+        last_entry.raw.push_str(line);
+        stats.continuations += 1;
+
+        if line.starts_with(TRACEBACK_HEADER) {
+            in_traceback = true;
+        }
+        if in_traceback || (last_entry.traceback.is_some() && continues_traceback(line)) {
+            let traceback = last_entry.traceback.get_or_insert_with(String::new);
+            if !traceback.is_empty() {
+                traceback.push('\n');
+            }
+            traceback.push_str(line);
+            last_entry.lines += 1;
+            // Frames are indented, the first unindented line is the exception
+            if !line.is_empty()
+                && !line.starts_with(char::is_whitespace)
+                && !line.starts_with(TRACEBACK_HEADER)
+            {
+                in_traceback = false;
+            }
+            continue;
+        }
+
+        last_entry.message.push('\n');
+        last_entry.message.push_str(line);
+        last_entry.lines += 1;
+    }
+    // Without any entry the undated lines take the timestamp of a dated line before them, or
+    // of the first one, and are left out when no line has a timestamp to place them at
+    let mut previous = pending.iter().find_map(|(timestamp, _)| *timestamp);
+    for (timestamp, line) in pending {
+        previous = timestamp.or(previous);
+        if let Some(timestamp) = previous {
+            entries.push(unparsed_entry(&line, timestamp));
+        }
+    }
+
+    Ok((entries, size, stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(data: &[u8]) -> (Vec<LogEntry>, usize, LineStats) {
+        process_log_file(BufReader::new(data), &ParseOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn log_level_aliases_and_severity() {
+        for (name, level) in [
+            ("warning", LogLevel::Warn),
+            ("W", LogLevel::Warn),
+            ("err", LogLevel::Error),
+            ("crit", LogLevel::Error),
+            (" Critical ", LogLevel::Error),
+            ("notice", LogLevel::Info),
+            ("verbose", LogLevel::Trace),
+            ("0", LogLevel::Error),
+            ("3", LogLevel::Error),
+            ("4", LogLevel::Warn),
+            ("6", LogLevel::Info),
+            ("7", LogLevel::Debug),
+            ("8", LogLevel::Unknown),
+            ("success", LogLevel::Unknown),
+        ] {
+            assert_eq!(LogLevel::from_str(name), level, "{name}");
+        }
+
+        let mut levels = vec![
+            LogLevel::Info,
+            LogLevel::Error,
+            LogLevel::Unknown,
+            LogLevel::Debug,
+            LogLevel::Warn,
+            LogLevel::Trace,
+        ];
+        levels.sort();
+        assert_eq!(
+            levels,
+            vec![
+                LogLevel::Unknown,
+                LogLevel::Trace,
+                LogLevel::Debug,
+                LogLevel::Info,
+                LogLevel::Warn,
+                LogLevel::Error,
+            ]
+        );
+    }
+
+    #[test]
+    fn processing_order_is_oldest_first() {
+        let mut writer = zip::ZipWriter::new(io::Cursor::new(Vec::new()));
+        let names = [
+            "service/service.log",
+            "other/other.log.1",
+            "service/service.log.1",
+            "service/service.log.2.gz",
+            "other/other.log",
+        ];
+        for name in names {
+            writer
+                .start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+        }
+        let archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
 
-        last_entry.message.push_str(&format!("\n{}", &line));
+        let order: Vec<&str> = processing_order(&archive)
+            .into_iter()
+            .map(|index| archive.name_for_index(index).unwrap())
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                "other/other.log.1",
+                "other/other.log",
+                "service/service.log.2.gz",
+                "service/service.log.1",
+                "service/service.log",
+            ]
+        );
     }
 
-    Ok((entries, size))
+    #[test]
+    fn process_log_file_entries() {
+        let data = b"orphan line before the first entry\n\
+            2024-03-12T10:00:00.123Z | INFO     | foo:bar:12 - hello\n\
+            \x20 continued line\n\
+            2024-03-12T10:00:01.123Z | ERROR    | foo:bar:13 - boom\n\
+            2024-03-12T10:00:02.123Z | WARNING  | foo:bar:14 - careful\n";
+        let (entries, size, stats) = process(data);
+
+        let summary: Vec<(LogLevel, &str)> = entries
+            .iter()
+            .map(|entry| (entry.level.clone(), entry.message.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (LogLevel::Info, "hello\n  continued line"),
+                (LogLevel::Error, "boom"),
+                (LogLevel::Warn, "careful"),
+            ]
+        );
+        assert_eq!(entries[0].component.as_deref(), Some("foo:bar:12"));
+        assert_eq!(entries[0].lines, 2);
+        assert_eq!(
+            entries[1].timestamp,
+            "2024-03-12T10:00:01.123Z".parse::<DateTime<Utc>>().unwrap()
+        );
+
+        let lines = String::from_utf8_lossy(data);
+        assert_eq!(size, lines.lines().map(str::len).sum::<usize>());
+        assert_eq!(stats.lines, 5);
+        assert_eq!(stats.parsed, 3);
+        assert_eq!(stats.continuations, 1);
+        assert_eq!(stats.unparsed, 1);
+    }
+
+    #[test]
+    fn process_log_file_keeps_unparsed_lines() {
+        let options = ParseOptions {
+            keep_unparsed: true,
+            ..Default::default()
+        };
+        let data = b"orphan\n2024-03-12T10:00:00Z | INFO | foo:bar:12 - hello\n";
+        let (entries, _, stats) = process_log_file(BufReader::new(&data[..]), &options).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].level, LogLevel::Unknown);
+        assert_eq!(entries[0].message, "orphan");
+        assert_eq!(entries[0].timestamp, entries[1].timestamp);
+        assert_eq!(stats.unparsed, 1);
+    }
+
+    #[test]
+    fn process_log_file_places_lines_without_entries() {
+        let options = ParseOptions {
+            keep_unparsed: true,
+            ..Default::default()
+        };
+        let data = b"starting\nboot at 2024-03-12 10:00:00\nready\n";
+        let (entries, _, _) = process_log_file(BufReader::new(&data[..]), &options).unwrap();
+        let boot = find_timestamp("2024-03-12 10:00:00").unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().all(|entry| entry.timestamp == boot));
+
+        let data = b"starting\nready\n";
+        let (entries, _, stats) = process_log_file(BufReader::new(&data[..]), &options).unwrap();
+        assert!(entries.is_empty());
+        assert_eq!(stats.unparsed, 2);
+    }
+
+    #[test]
+    fn message_pattern_groups_similar_lines() {
+        assert_eq!(
+            message_pattern("Sent 42 bytes to 192.168.2.2 in 0.5s"),
+            message_pattern("Sent 7 bytes to 10.0.0.1 in 12.25s"),
+        );
+        assert_eq!(
+            message_pattern(
+                "Frame 0x1f3a from a3f9c2e1, id 550e8400-e29b-41d4-a716-446655440000\nmore"
+            ),
+            "Frame <*> from <*>, id <*>"
+        );
+        assert_eq!(
+            message_pattern("version 4.1.2 on port 8080"),
+            "version <*> on port <*>"
+        );
+        assert_eq!(message_pattern("Connected to face"), "Connected to face");
+    }
+
+    #[test]
+    fn message_fields_pairs() {
+        assert_eq!(
+            message_fields(r#"mode=GUIDED reason="lost \"link\"" armed battery= x==y"#),
+            vec![
+                ("mode", "GUIDED"),
+                ("reason", r#"lost \"link\""#),
+                ("battery", ""),
+            ]
+        );
+        assert_eq!(message_fields("no fields here"), vec![]);
+    }
+
+    #[test]
+    fn process_log_file_keeps_invalid_utf8_lines() {
+        let data = b"2024-03-12T10:00:00Z | INFO | foo:bar:12 - caf\xe9 open\n\
+            2024-03-12T10:00:01Z | INFO | foo:bar:13 - next\n";
+        let (entries, _, stats) = process(data);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].message, "caf\u{fffd} open");
+        assert_eq!(entries[1].message, "next");
+        assert_eq!(stats.parsed, 2);
+    }
+
+    #[test]
+    fn process_log_file_mixes_json_and_text_lines() {
+        let data = br#"{"timestamp": "2024-03-12T10:00:00Z", "level": "warn", "target": "mavlink", "message": "late", "delay": 3}
+2024-03-12T10:00:01Z | INFO | foo:bar:12 - plain
+{"timestamp": "2024-03-12T10:00:02Z", "level": 3, "message": "numeric level"}
+{not json
+"#;
+        let (entries, _, stats) = process(data);
+
+        let summary: Vec<(LogLevel, Option<&str>, &str)> = entries
+            .iter()
+            .map(|entry| {
+                let component = entry.component.as_deref();
+                (entry.level.clone(), component, entry.message.as_str())
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (LogLevel::Warn, Some("mavlink"), "late delay=3"),
+                (LogLevel::Info, Some("foo:bar:12"), "plain"),
+                (LogLevel::Error, None, "numeric level\n{not json"),
+            ]
+        );
+        assert_eq!(stats.parsed, 3);
+        assert_eq!(stats.continuations, 1);
+    }
+
+    #[test]
+    fn parse_timestamp_precisions_and_offsets() {
+        let timestamp = |line: &str| LogEntry::parse(line, &[]).unwrap().timestamp;
+        let expected = |text: &str| text.parse::<DateTime<Utc>>().unwrap();
+
+        assert_eq!(
+            timestamp("2024-03-12T10:00:00Z | INFO | hello"),
+            expected("2024-03-12T10:00:00Z")
+        );
+        assert_eq!(
+            timestamp("2024-03-12T10:00:00.123456Z | INFO | hello"),
+            expected("2024-03-12T10:00:00.123456Z")
+        );
+        assert_eq!(
+            timestamp("2024-03-12T10:00:00+02:00 | INFO | hello"),
+            expected("2024-03-12T08:00:00Z")
+        );
+        assert_eq!(
+            timestamp("2024-03-12T10:00:00.5-03:30  INFO  hello"),
+            expected("2024-03-12T13:30:00.5Z")
+        );
+    }
 }
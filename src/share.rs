@@ -0,0 +1,87 @@
+// Only the web build keeps the view in the URL
+#![cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+
+use crate::parser::LogLevel;
+use chrono::{DateTime, SecondsFormat, Utc};
+use strum::IntoEnumIterator;
+
+// What a shared link opens once the same bundle is loaded
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct View {
+    pub service: String,
+    pub filter: String,
+    pub use_regex: bool,
+    pub is_search: bool,
+    pub levels: Vec<LogLevel>,
+    pub range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+impl View {
+    pub fn to_hash(&self) -> String {
+        let levels: Vec<String> = self.levels.iter().map(ToString::to_string).collect();
+        let mut serializer = form_urlencoded::Serializer::new(String::new());
+        serializer
+            .append_pair("service", &self.service)
+            .append_pair("filter", &self.filter)
+            .append_pair("regex", if self.use_regex { "1" } else { "0" })
+            .append_pair("search", if self.is_search { "1" } else { "0" })
+            .append_pair("levels", &levels.join(","));
+        if let Some((first, second)) = self.range {
+            serializer
+                .append_pair("from", &first.to_rfc3339_opts(SecondsFormat::Secs, true))
+                .append_pair("to", &second.to_rfc3339_opts(SecondsFormat::Secs, true));
+        }
+        serializer.finish()
+    }
+
+    // Links without a service don't open anything
+    pub fn from_hash(hash: &str) -> Option<Self> {
+        let mut view = View {
+            use_regex: true,
+            is_search: true,
+            levels: LogLevel::iter()
+                .filter(|level| *level != LogLevel::Unknown)
+                .collect(),
+            ..Default::default()
+        };
+        let (mut from, mut to) = (None, None);
+        for (key, value) in form_urlencoded::parse(hash.trim_start_matches('#').as_bytes()) {
+            match key.as_ref() {
+                "service" => view.service = value.into_owned(),
+                "filter" => view.filter = value.into_owned(),
+                "regex" => view.use_regex = value == "1",
+                "search" => view.is_search = value == "1",
+                "levels" => {
+                    view.levels = value
+                        .split(',')
+                        .filter_map(|name| LogLevel::iter().find(|level| level.to_string() == name))
+                        .collect()
+                }
+                "from" => from = DateTime::parse_from_rfc3339(&value).ok(),
+                "to" => to = DateTime::parse_from_rfc3339(&value).ok(),
+                _ => {}
+            }
+        }
+        if let (Some(from), Some(to)) = (from, to) {
+            view.range = Some((from.to_utc(), to.to_utc()));
+        }
+        (!view.service.is_empty()).then_some(view)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read() -> Option<View> {
+    View::from_hash(&web_sys::window()?.location().hash().ok()?)
+}
+
+// Replaces the hash without adding to the history of the page
+#[cfg(target_arch = "wasm32")]
+pub fn write(hash: &str) {
+    let Some(history) = web_sys::window().and_then(|window| window.history().ok()) else {
+        return;
+    };
+    let url = format!("#{hash}");
+    if let Err(e) = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url)) {
+        log::warn!("Failed to write the view to the URL: {e:?}");
+    }
+}
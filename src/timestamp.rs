@@ -0,0 +1,71 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Utc};
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum TimestampFormat {
+    #[default]
+    Full,
+    TimeOnly,
+    // Time since the earliest entry of the tab
+    Relative,
+    EpochMillis,
+    Custom(String),
+}
+
+impl std::fmt::Display for TimestampFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimestampFormat::Full => write!(f, "Full"),
+            TimestampFormat::TimeOnly => write!(f, "Time only"),
+            TimestampFormat::Relative => write!(f, "Relative to log start"),
+            TimestampFormat::EpochMillis => write!(f, "Epoch milliseconds"),
+            TimestampFormat::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+impl TimestampFormat {
+    pub const PRESETS: [TimestampFormat; 4] = [
+        TimestampFormat::Full,
+        TimestampFormat::TimeOnly,
+        TimestampFormat::Relative,
+        TimestampFormat::EpochMillis,
+    ];
+
+    pub const DEFAULT_CUSTOM: &'static str = "%Y-%m-%d %H:%M:%S%.3f";
+
+    // chrono panics when displaying an invalid format, so custom formats are checked first
+    pub fn is_valid(format: &str) -> bool {
+        !StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+    }
+
+    pub fn format(&self, timestamp: DateTime<Utc>, start: DateTime<Utc>) -> String {
+        match self {
+            TimestampFormat::Full => timestamp.to_string(),
+            TimestampFormat::TimeOnly => timestamp.format("%H:%M:%S%.3f").to_string(),
+            TimestampFormat::Relative => {
+                let delta = timestamp - start;
+                let sign = if delta < chrono::TimeDelta::zero() {
+                    "-"
+                } else {
+                    "+"
+                };
+                let millis = delta.num_milliseconds().unsigned_abs();
+                let seconds = millis / 1000;
+                let (hours, minutes) = (seconds / 3600, seconds / 60 % 60);
+                let seconds = format!("{:02}.{:03}", seconds % 60, millis % 1000);
+                // Hours are only shown once reached, e.g. +00:12.345 and +1:02:03.000
+                if hours > 0 {
+                    format!("{sign}{hours}:{minutes:02}:{seconds}")
+                } else {
+                    format!("{sign}{minutes:02}:{seconds}")
+                }
+            }
+            TimestampFormat::EpochMillis => timestamp.timestamp_millis().to_string(),
+            TimestampFormat::Custom(format) if Self::is_valid(format) => {
+                timestamp.format(format).to_string()
+            }
+            TimestampFormat::Custom(_) => timestamp.to_string(),
+        }
+    }
+}